@@ -0,0 +1,100 @@
+use crate::config;
+use crate::error::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ChangeTracker {
+    #[serde(default)]
+    markers: HashMap<String, String>,
+}
+
+impl ChangeTracker {
+    pub fn path() -> PathBuf {
+        config::config_dir().join("change_state.json")
+    }
+
+    pub fn load() -> Self {
+        Self::load_from(&Self::path())
+    }
+
+    fn load_from(path: &Path) -> Self {
+        if !path.exists() {
+            return Self::default();
+        }
+        match fs::read_to_string(&path).map(|c| serde_json::from_str(&c)) {
+            Ok(Ok(tracker)) => tracker,
+            Ok(Err(e)) => {
+                warn!("Ignoring unreadable change state {:?}: {}", path, e);
+                Self::default()
+            }
+            Err(e) => {
+                warn!("Failed to read change state {:?}: {}", path, e);
+                Self::default()
+            }
+        }
+    }
+
+    fn save_to(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Stores markers of databases whose archive was written, as returned by [`Self::key`]
+    /// and the marker. Called once per run, after the archive is complete, so a failed run
+    /// never causes the next one to skip a database.
+    pub fn commit(markers: &[(String, String)]) -> Result<()> {
+        Self::commit_to(&Self::path(), markers)
+    }
+
+    fn commit_to(path: &Path, markers: &[(String, String)]) -> Result<()> {
+        if markers.is_empty() {
+            return Ok(());
+        }
+        let mut tracker = Self::load_from(path);
+        tracker.markers.extend(markers.iter().cloned());
+        tracker.save_to(path)
+    }
+
+    pub fn key(connection: &str, database: &str) -> String {
+        format!("{}/{}", connection, database)
+    }
+
+    pub fn is_unchanged(&self, connection: &str, database: &str, marker: &str) -> bool {
+        self.markers
+            .get(&Self::key(connection, database))
+            .is_some_and(|m| m == marker)
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_commit_advances_only_given_markers() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("change_state.json");
+        let key = ChangeTracker::key("prod", "shop");
+        ChangeTracker::commit_to(&path, &[(key.clone(), "m1".to_string())]).unwrap();
+
+        // A failed run commits nothing, so the previous marker stays.
+        ChangeTracker::commit_to(&path, &[]).unwrap();
+        let tracker = ChangeTracker::load_from(&path);
+        assert!(tracker.is_unchanged("prod", "shop", "m1"));
+        assert!(!tracker.is_unchanged("prod", "shop", "m2"));
+
+        let other = ChangeTracker::key("prod", "crm");
+        ChangeTracker::commit_to(&path, &[(key, "m2".to_string()), (other, "c1".to_string())]).unwrap();
+        let tracker = ChangeTracker::load_from(&path);
+        assert!(tracker.is_unchanged("prod", "shop", "m2"));
+        assert!(tracker.is_unchanged("prod", "crm", "c1"));
+    }
+}
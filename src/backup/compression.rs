@@ -1,10 +1,65 @@
 use crate::error::{BackupError, Result};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
 use std::fs::File;
-use std::io::{BufReader, BufWriter, Read, Write};
+use std::io::{BufReader, BufWriter, Read, Seek, Write};
 use std::path::{Path, PathBuf};
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 use zip::write::SimpleFileOptions;
-use zip::ZipWriter;
+use zip::{ZipArchive, ZipWriter};
+
+pub const MANIFEST_NAME: &str = "MANIFEST.sha256";
+
+pub const DEFAULT_COMPRESSION_LEVEL: i64 = 6;
+
+/// The zip format stores the archive comment's length in 16 bits.
+const MAX_COMMENT_LEN: usize = u16::MAX as usize;
+
+/// Extension of the manifest listing the volumes of a split archive.
+pub const PARTS_MANIFEST_EXTENSION: &str = "parts";
+
+/// Level 0 stores entries uncompressed; 1-9 are deflate levels.
+fn file_options(level: i64) -> SimpleFileOptions {
+    if level == 0 {
+        SimpleFileOptions::default().compression_method(zip::CompressionMethod::Stored)
+    } else {
+        SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Deflated)
+            .compression_level(Some(level))
+    }
+}
+
+/// Forwards writes to `inner` while hashing every byte that passes through.
+struct HashingWriter<W: Write> {
+    inner: W,
+    hasher: Sha256,
+}
+
+impl<W: Write> HashingWriter<W> {
+    fn new(inner: W) -> Self {
+        Self {
+            inner,
+            hasher: Sha256::new(),
+        }
+    }
+
+    fn finish(mut self) -> Result<String> {
+        self.inner.flush()?;
+        Ok(format!("{:x}", self.hasher.finalize()))
+    }
+}
+
+impl<W: Write> Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hasher.update(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
 
 pub fn compress_to_zip(source_path: &Path, dest_path: &Path, archive_filename: &str) -> Result<()> {
     info!("Compressing {} to {}", source_path.display(), dest_path.display());
@@ -44,11 +99,27 @@ pub fn compress_to_zip(source_path: &Path, dest_path: &Path, archive_filename: &
     Ok(())
 }
 
-pub fn compress_multiple_to_zip(source_files: &[(PathBuf, String)], dest_path: &Path) -> Result<()> {
+pub fn compress_multiple_to_zip(source_files: &[(PathBuf, String)], dest_path: &Path) -> Result<String> {
     compress_multiple_to_zip_silent(source_files, dest_path, false)
 }
 
-pub fn compress_multiple_to_zip_silent(source_files: &[(PathBuf, String)], dest_path: &Path, silent: bool) -> Result<()> {
+/// Compresses `source_files` into `dest_path` and returns the SHA256 of the archive.
+///
+/// The archive is written in streaming mode so bytes reach the file strictly in
+/// order, which lets the hash be computed as it is written instead of re-reading it.
+pub fn compress_multiple_to_zip_silent(source_files: &[(PathBuf, String)], dest_path: &Path, silent: bool) -> Result<String> {
+    compress_multiple_to_zip_with_level(source_files, dest_path, silent, DEFAULT_COMPRESSION_LEVEL, None)
+}
+
+/// Like [`compress_multiple_to_zip_silent`], with a compression level and an optional
+/// archive comment (shown by `unzip -z`).
+pub fn compress_multiple_to_zip_with_level(
+    source_files: &[(PathBuf, String)],
+    dest_path: &Path,
+    silent: bool,
+    level: i64,
+    comment: Option<&str>,
+) -> Result<String> {
     if !silent {
         info!("Compressing {} files to {}", source_files.len(), dest_path.display());
     }
@@ -58,14 +129,13 @@ pub fn compress_multiple_to_zip_silent(source_files: &[(PathBuf, String)], dest_
     }
 
     let dest_file = File::create(dest_path)?;
-    let buffered_writer = BufWriter::new(dest_file);
-    let mut zip = ZipWriter::new(buffered_writer);
+    let hashing_writer = HashingWriter::new(BufWriter::new(dest_file));
+    let mut zip = ZipWriter::new_stream(hashing_writer);
 
-    let options = SimpleFileOptions::default()
-        .compression_method(zip::CompressionMethod::Deflated)
-        .compression_level(Some(6));
+    let options = file_options(level);
 
     let mut buffer = vec![0u8; 64 * 1024];
+    let mut manifest = String::new();
 
     for (source_path, archive_name) in source_files {
         if !silent {
@@ -76,17 +146,29 @@ pub fn compress_multiple_to_zip_silent(source_files: &[(PathBuf, String)], dest_
 
         let source_file = File::open(source_path)?;
         let mut reader = BufReader::new(source_file);
+        let mut hasher = Sha256::new();
 
         loop {
             let bytes_read = reader.read(&mut buffer)?;
             if bytes_read == 0 {
                 break;
             }
+            hasher.update(&buffer[..bytes_read]);
             zip.write_all(&buffer[..bytes_read])?;
         }
+        manifest.push_str(&format!("{:x}  {}\n", hasher.finalize(), archive_name));
     }
 
-    zip.finish()?;
+    zip.start_file(MANIFEST_NAME, options)?;
+    zip.write_all(manifest.as_bytes())?;
+    match comment {
+        Some(comment) if comment.len() > MAX_COMMENT_LEN => {
+            warn!("Archive comment for {} is too long ({} bytes), leaving it out", dest_path.display(), comment.len());
+        }
+        Some(comment) => zip.set_comment(comment),
+        None => {}
+    }
+    let archive_hash = zip.finish()?.into_inner().finish()?;
 
     if !silent {
         let dest_size = std::fs::metadata(dest_path)?.len();
@@ -97,12 +179,174 @@ pub fn compress_multiple_to_zip_silent(source_files: &[(PathBuf, String)], dest_
         );
     }
 
+    Ok(archive_hash)
+}
+
+pub fn read_manifest<R: Read + Seek>(archive: &mut ZipArchive<R>) -> Result<Option<HashMap<String, String>>> {
+    let mut contents = String::new();
+    match archive.by_name(MANIFEST_NAME) {
+        Ok(mut file) => {
+            file.read_to_string(&mut contents)?;
+        }
+        Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+        Err(e) => return Err(e.into()),
+    }
+
+    let entries = contents
+        .lines()
+        .filter_map(|line| line.split_once("  "))
+        .map(|(hash, name)| (name.to_string(), hash.to_string()))
+        .collect();
+    Ok(Some(entries))
+}
+
+/// The archive-level comment, if there is one.
+pub fn read_comment(archive_path: &Path) -> Result<Option<String>> {
+    let archive = ZipArchive::new(File::open(archive_path)?)?;
+    let comment = archive.comment();
+    Ok((!comment.is_empty()).then(|| String::from_utf8_lossy(comment).to_string()))
+}
+
+/// Checks that the archive's central directory reads back and lists exactly the entries
+/// in its manifest, so a truncated archive is caught before it is uploaded or recorded.
+/// With `rehash`, every entry is also re-read and checked against its hash.
+pub fn verify_archive(archive_path: &Path, rehash: bool) -> Result<()> {
+    let mut archive = ZipArchive::new(File::open(archive_path)?)?;
+    let manifest = read_manifest(&mut archive)?
+        .ok_or_else(|| BackupError::Compression(format!("{} has no manifest", archive_path.display())))?;
+    let entries: Vec<&str> = archive.file_names().filter(|name| *name != MANIFEST_NAME).collect();
+    if entries.len() != manifest.len() || entries.iter().any(|name| !manifest.contains_key(*name)) {
+        return Err(BackupError::Compression(format!(
+            "Entries of {} don't match its manifest",
+            archive_path.display()
+        )));
+    }
+    if !rehash {
+        return Ok(());
+    }
+    for (name, expected) in &manifest {
+        let mut out = HashingWriter::new(std::io::sink());
+        std::io::copy(&mut archive.by_name(name)?, &mut out)?;
+        if out.finish()? != *expected {
+            return Err(BackupError::Compression(format!(
+                "Checksum mismatch for {} in {}",
+                name,
+                archive_path.display()
+            )));
+        }
+    }
     Ok(())
 }
 
-pub fn calculate_sha256(file_path: &Path) -> Result<String> {
-    use sha2::{Digest, Sha256};
+/// Volumes written by [`split_archive`], each with its SHA-256, and their manifest.
+#[derive(Debug)]
+pub struct SplitArchive {
+    pub parts: Vec<(PathBuf, String)>,
+    pub manifest: PathBuf,
+}
 
+/// Splits `archive_path` into `{name}.001`, `{name}.002`, ... of at most `volume_size`
+/// bytes next to it, plus a `{name}.parts` manifest with the hash of every volume and,
+/// on its last line, of the whole archive. The archive itself is left in place.
+pub fn split_archive(archive_path: &Path, volume_size: u64) -> Result<SplitArchive> {
+    if volume_size == 0 {
+        return Err(BackupError::Compression("Volume size must be greater than 0".to_string()));
+    }
+    let archive_name = archive_path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .ok_or_else(|| BackupError::Compression(format!("Invalid archive path {}", archive_path.display())))?;
+
+    let mut reader = BufReader::new(File::open(archive_path)?);
+    let mut archive_hasher = Sha256::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+    let mut parts = Vec::new();
+    loop {
+        let part_path = archive_path.with_file_name(format!("{}.{:03}", archive_name, parts.len() + 1));
+        let mut out = HashingWriter::new(BufWriter::new(File::create(&part_path)?));
+        let mut written = 0u64;
+        while written < volume_size {
+            let want = buffer.len().min((volume_size - written) as usize);
+            let bytes_read = reader.read(&mut buffer[..want])?;
+            if bytes_read == 0 {
+                break;
+            }
+            out.write_all(&buffer[..bytes_read])?;
+            archive_hasher.update(&buffer[..bytes_read]);
+            written += bytes_read as u64;
+        }
+        let hash = out.finish()?;
+        if written == 0 {
+            std::fs::remove_file(&part_path)?;
+            break;
+        }
+        parts.push((part_path, hash));
+        if written < volume_size {
+            break;
+        }
+    }
+
+    let mut manifest = String::new();
+    for (path, hash) in &parts {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        manifest.push_str(&format!("{}  {}\n", hash, name));
+    }
+    manifest.push_str(&format!("{:x}  {}\n", archive_hasher.finalize(), archive_name));
+    let manifest_path = archive_path.with_file_name(format!("{}.{}", archive_name, PARTS_MANIFEST_EXTENSION));
+    std::fs::write(&manifest_path, manifest)?;
+
+    debug!("Split {} into {} volumes", archive_path.display(), parts.len());
+    Ok(SplitArchive {
+        parts,
+        manifest: manifest_path,
+    })
+}
+
+/// Reassembles a split archive from its `.parts` manifest, verifying every volume and
+/// the result. Writes to `dest`, or next to the manifest under the original name.
+pub fn join_archive(manifest_path: &Path, dest: Option<&Path>) -> Result<PathBuf> {
+    let contents = std::fs::read_to_string(manifest_path)?;
+    let mut entries: Vec<(&str, &str)> = contents.lines().filter_map(|line| line.split_once("  ")).collect();
+    let (archive_hash, archive_name) = entries
+        .pop()
+        .ok_or_else(|| BackupError::Compression(format!("{} lists no volumes", manifest_path.display())))?;
+    let dir = manifest_path.parent().unwrap_or_else(|| Path::new("."));
+    let dest = match dest {
+        Some(path) => path.to_path_buf(),
+        None => dir.join(volume_file_name(archive_name)?),
+    };
+
+    if let Err(e) = write_joined(dir, &entries, archive_hash, &dest) {
+        let _ = std::fs::remove_file(&dest);
+        return Err(e);
+    }
+    Ok(dest)
+}
+
+/// Names come from the manifest, so never let them point outside its directory.
+fn volume_file_name(name: &str) -> Result<PathBuf> {
+    Path::new(name)
+        .file_name()
+        .map(PathBuf::from)
+        .ok_or_else(|| BackupError::Compression(format!("Invalid file name '{}' in manifest", name)))
+}
+
+fn write_joined(dir: &Path, volumes: &[(&str, &str)], archive_hash: &str, dest: &Path) -> Result<()> {
+    let mut out = HashingWriter::new(BufWriter::new(File::create(dest)?));
+    for (hash, name) in volumes {
+        let path = dir.join(volume_file_name(name)?);
+        if calculate_sha256(&path)? != *hash {
+            return Err(BackupError::Compression(format!("Checksum mismatch for volume {}", name)));
+        }
+        std::io::copy(&mut File::open(&path)?, &mut out)?;
+    }
+    if out.finish()? != archive_hash {
+        return Err(BackupError::Compression(format!("Checksum mismatch for joined {}", dest.display())));
+    }
+    Ok(())
+}
+
+pub fn calculate_sha256(file_path: &Path) -> Result<String> {
     let file = File::open(file_path)?;
     let mut reader = BufReader::new(file);
     let mut hasher = Sha256::new();
@@ -142,6 +386,83 @@ mod tests {
         assert!(dest_meta.len() > 0);
     }
 
+    #[test]
+    fn test_compress_multiple_writes_manifest() {
+        let dir = tempdir().unwrap();
+        let source = dir.path().join("db.sql");
+        let dest = dir.path().join("multi.zip");
+
+        let mut file = File::create(&source).unwrap();
+        file.write_all(b"hello world").unwrap();
+
+        compress_multiple_to_zip_silent(&[(source, "db.sql".to_string())], &dest, true).unwrap();
+
+        let mut archive = ZipArchive::new(File::open(&dest).unwrap()).unwrap();
+        let manifest = read_manifest(&mut archive).unwrap().unwrap();
+        assert_eq!(
+            manifest.get("db.sql").map(String::as_str),
+            Some("b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9")
+        );
+    }
+
+    #[test]
+    fn test_compress_multiple_hash_matches_file() {
+        let dir = tempdir().unwrap();
+        let source = dir.path().join("db.sql");
+        let dest = dir.path().join("multi.zip");
+
+        let mut file = File::create(&source).unwrap();
+        file.write_all(b"INSERT INTO t VALUES (1);\n".repeat(10_000).as_slice()).unwrap();
+
+        let hash = compress_multiple_to_zip_silent(&[(source, "db.sql".to_string())], &dest, true).unwrap();
+
+        assert_eq!(hash, calculate_sha256(&dest).unwrap());
+        assert!(ZipArchive::new(File::open(&dest).unwrap()).is_ok());
+        verify_archive(&dest, false).unwrap();
+        verify_archive(&dest, true).unwrap();
+        assert_eq!(read_comment(&dest).unwrap(), None);
+
+        let len = std::fs::metadata(&dest).unwrap().len();
+        File::options().write(true).open(&dest).unwrap().set_len(len / 2).unwrap();
+        assert!(verify_archive(&dest, false).is_err());
+    }
+
+    #[test]
+    fn test_archive_comment() {
+        let dir = tempdir().unwrap();
+        let source = dir.path().join("db.sql");
+        let dest = dir.path().join("commented.zip");
+        std::fs::write(&source, b"SELECT 1;\n").unwrap();
+
+        let sources = [(source, "db.sql".to_string())];
+        compress_multiple_to_zip_with_level(&sources, &dest, true, 6, Some("{\"databases\":[\"db\"]}")).unwrap();
+        assert_eq!(read_comment(&dest).unwrap().as_deref(), Some("{\"databases\":[\"db\"]}"));
+    }
+
+    #[test]
+    fn test_split_and_join_archive() {
+        let dir = tempdir().unwrap();
+        let archive = dir.path().join("backup.zip");
+        let contents: Vec<u8> = (0..10_000u32).map(|i| (i % 251) as u8).collect();
+        std::fs::write(&archive, &contents).unwrap();
+
+        let split = split_archive(&archive, 4096).unwrap();
+        let sizes: Vec<u64> = split
+            .parts
+            .iter()
+            .map(|(path, _)| std::fs::metadata(path).unwrap().len())
+            .collect();
+        assert_eq!(sizes, vec![4096, 4096, 1808]);
+        assert_eq!(split.manifest, dir.path().join("backup.zip.parts"));
+
+        let joined = join_archive(&split.manifest, Some(&dir.path().join("joined.zip"))).unwrap();
+        assert_eq!(std::fs::read(&joined).unwrap(), contents);
+
+        std::fs::write(&split.parts[1].0, b"corrupted").unwrap();
+        assert!(join_archive(&split.manifest, Some(&dir.path().join("bad.zip"))).is_err());
+        assert!(!dir.path().join("bad.zip").exists());
+    }
+
     #[test]
     fn test_calculate_sha256() {
         let dir = tempdir().unwrap();
@@ -0,0 +1,314 @@
+use crate::config::AppConfig;
+use crate::error::Result;
+use crate::upload::{create_uploaders, BackupMetadata, PendingUploads, UploadStatus};
+use crate::web::{AppState, BackupEntry};
+use chrono::{DateTime, Utc};
+use fs2::FileExt;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+/// Extension of the marker kept next to an archive until its run is in the history.
+pub const PENDING_EXTENSION: &str = "pending";
+
+/// Extension given to archives whose compression never finished.
+const INCOMPLETE_EXTENSION: &str = "incomplete";
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArchiveStage {
+    Compressing,
+    Uploading,
+    Uploaded,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingRecord {
+    pub stage: ArchiveStage,
+    pub connection_name: String,
+    pub databases: Vec<String>,
+    pub timestamp: DateTime<Utc>,
+    #[serde(default)]
+    pub file_size: u64,
+    #[serde(default)]
+    pub file_hash: Option<String>,
+    #[serde(default)]
+    pub duration_secs: u64,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub uploads: Vec<UploadStatus>,
+}
+
+/// The marker of an archive being produced. Its file stays locked for as long as this
+/// value lives, so [`reconcile`] only ever picks up markers of runs that died.
+#[derive(Debug)]
+pub struct PendingArchive {
+    path: PathBuf,
+    file: File,
+    record: PendingRecord,
+}
+
+impl PendingArchive {
+    pub fn create(archive_path: &Path, record: PendingRecord) -> Result<Self> {
+        let path = marker_path(archive_path);
+        let file = OpenOptions::new().read(true).write(true).create(true).truncate(true).open(&path)?;
+        file.try_lock_exclusive()?;
+        let mut pending = Self { path, file, record };
+        pending.write()?;
+        Ok(pending)
+    }
+
+    pub fn update(&mut self, change: impl FnOnce(&mut PendingRecord)) -> Result<()> {
+        change(&mut self.record);
+        self.write()
+    }
+
+    /// Removes the marker once the run has been recorded (or its archive deleted).
+    pub fn clear(&self) {
+        let _ = fs::remove_file(&self.path);
+    }
+
+    fn write(&mut self) -> Result<()> {
+        let json = serde_json::to_vec_pretty(&self.record)?;
+        self.file.set_len(0)?;
+        self.file.seek(SeekFrom::Start(0))?;
+        self.file.write_all(&json)?;
+        self.file.sync_data()?;
+        Ok(())
+    }
+}
+
+fn marker_path(archive_path: &Path) -> PathBuf {
+    let mut name = archive_path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{}", PENDING_EXTENSION));
+    archive_path.with_file_name(name)
+}
+
+/// Settles markers left behind by runs that stopped before they were recorded. Archives
+/// that were fully written are added to the history, with uploads that never happened
+/// queued for retry; half-written ones are renamed to `.incomplete` so restores ignore
+/// them. Returns a notice for every archive that was handled.
+pub async fn reconcile(config: &AppConfig, app_state: &AppState) -> Vec<String> {
+    let targets: Vec<&str> = create_uploaders(&config.upload).iter().map(|u| u.name()).collect();
+    settle_markers(&config.local_backup_dir, &targets, &PendingUploads::path(), app_state).await
+}
+
+/// [`reconcile`] for the markers under `backup_dir`, queueing missing uploads to each of
+/// `targets` in the pending upload queue at `pending_path`.
+async fn settle_markers(backup_dir: &Path, targets: &[&str], pending_path: &Path, app_state: &AppState) -> Vec<String> {
+    let mut notices = Vec::new();
+    for marker in find_markers(backup_dir) {
+        let archive = marker.with_extension("");
+        let Ok(mut file) = OpenOptions::new().read(true).write(true).open(&marker) else {
+            continue;
+        };
+        if file.try_lock_exclusive().is_err() {
+            // A backup in this or another process is still working on it.
+            continue;
+        }
+        let mut contents = String::new();
+        let record = match file.read_to_string(&mut contents) {
+            Ok(_) => serde_json::from_str::<PendingRecord>(&contents).map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+        drop(file);
+
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                notices.push(format!(
+                    "{} has an unreadable backup marker ({}); check it and delete {}",
+                    archive.display(),
+                    e,
+                    marker.display()
+                ));
+                continue;
+            }
+        };
+        if !archive.exists() {
+            let _ = fs::remove_file(&marker);
+            continue;
+        }
+
+        if record.stage == ArchiveStage::Compressing {
+            let mut name = archive.file_name().unwrap_or_default().to_os_string();
+            name.push(format!(".{}", INCOMPLETE_EXTENSION));
+            let incomplete = archive.with_file_name(name);
+            match fs::rename(&archive, &incomplete) {
+                Ok(()) => notices.push(format!(
+                    "{} was left half-written by an interrupted backup; renamed to {}",
+                    archive.display(),
+                    incomplete.display()
+                )),
+                Err(e) => {
+                    notices.push(format!("Failed to flag incomplete archive {}: {}", archive.display(), e));
+                    continue;
+                }
+            }
+        } else {
+            register(targets, pending_path, app_state, &archive, record).await;
+            notices.push(format!("Re-registered {} from an interrupted backup", archive.display()));
+        }
+        let _ = fs::remove_file(&marker);
+    }
+    notices
+}
+
+async fn register(targets: &[&str], pending_path: &Path, app_state: &AppState, archive: &Path, record: PendingRecord) {
+    let mut uploads = record.uploads.clone();
+    let mut error = None;
+    if record.stage == ArchiveStage::Uploading {
+        let reason = "Interrupted before the upload finished";
+        error = Some(reason.to_string());
+        let metadata = BackupMetadata {
+            databases: record.databases.clone(),
+            connection_name: record.connection_name.clone(),
+            timestamp: record.timestamp,
+            file_size: record.file_size,
+            file_hash: record.file_hash.clone(),
+            duration_secs: record.duration_secs,
+            file_path: archive.to_string_lossy().to_string(),
+            tags: record.tags.clone(),
+            binlog_positions: Default::default(),
+        };
+        for target in targets {
+            if uploads.iter().any(|u| u.target == *target) {
+                continue;
+            }
+            PendingUploads::enqueue_to(pending_path, target, &metadata, reason);
+            uploads.push(UploadStatus {
+                target: target.to_string(),
+                ok: false,
+                error: Some(reason.to_string()),
+            });
+        }
+    }
+
+    app_state
+        .add_backup_entry(BackupEntry {
+            timestamp: record.timestamp,
+            connection_name: record.connection_name,
+            databases: record.databases,
+            success: true,
+            file_size: record.file_size,
+            duration_secs: record.duration_secs,
+            error,
+            tags: record.tags,
+            uploads,
+            file_path: Some(archive.to_string_lossy().to_string()),
+            pinned: false,
+        })
+        .await;
+}
+
+/// Markers in the backup directory and its per-connection subdirectories.
+fn find_markers(backup_dir: &Path) -> Vec<PathBuf> {
+    let mut dirs = vec![backup_dir.to_path_buf()];
+    if let Ok(entries) = fs::read_dir(backup_dir) {
+        dirs.extend(entries.flatten().map(|e| e.path()).filter(|p| p.is_dir()));
+    }
+    dirs.iter()
+        .filter_map(|dir| fs::read_dir(dir).ok())
+        .flat_map(|entries| entries.flatten().map(|e| e.path()))
+        .filter(|p| p.is_file() && p.extension().is_some_and(|ext| ext == PENDING_EXTENSION))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(stage: ArchiveStage, uploads: Vec<UploadStatus>) -> PendingRecord {
+        PendingRecord {
+            stage,
+            connection_name: "prod".to_string(),
+            databases: vec!["shop".to_string()],
+            timestamp: Utc::now(),
+            file_size: 3,
+            file_hash: None,
+            duration_secs: 1,
+            tags: Vec::new(),
+            uploads,
+        }
+    }
+
+    /// An archive in `{dir}/prod` with the marker a run in `stage` would have left.
+    fn leave_marker(dir: &Path, stage: ArchiveStage, uploads: Vec<UploadStatus>) -> PathBuf {
+        let connection_dir = dir.join("prod");
+        fs::create_dir_all(&connection_dir).unwrap();
+        let archive = connection_dir.join("backup_prod_20240101_000000.zip");
+        fs::write(&archive, b"zip").unwrap();
+        drop(PendingArchive::create(&archive, record(stage, uploads)).unwrap());
+        archive
+    }
+
+    #[tokio::test]
+    async fn test_compressing_archive_is_flagged_incomplete() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = leave_marker(dir.path(), ArchiveStage::Compressing, Vec::new());
+        let app_state = AppState::new(String::new(), String::new());
+
+        let notices = settle_markers(dir.path(), &[], &dir.path().join("pending.json"), &app_state).await;
+        assert_eq!(notices.len(), 1);
+        assert!(!archive.exists() && !marker_path(&archive).exists());
+        assert!(archive.with_file_name("backup_prod_20240101_000000.zip.incomplete").exists());
+        assert!(app_state.history.read().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_uploading_archive_is_registered_and_queued() {
+        let dir = tempfile::tempdir().unwrap();
+        let done = UploadStatus {
+            target: "Discord".to_string(),
+            ok: true,
+            error: None,
+        };
+        let archive = leave_marker(dir.path(), ArchiveStage::Uploading, vec![done]);
+        let pending_path = dir.path().join("pending.json");
+        let app_state = AppState::new(String::new(), String::new());
+
+        let notices = settle_markers(dir.path(), &["Discord", "S3"], &pending_path, &app_state).await;
+        assert_eq!(notices.len(), 1);
+        assert!(archive.exists() && !marker_path(&archive).exists());
+
+        let history = app_state.history.read().await;
+        assert_eq!(history.len(), 1);
+        assert!(history[0].success && history[0].error.is_some());
+        let targets: Vec<(&str, bool)> = history[0].uploads.iter().map(|u| (u.target.as_str(), u.ok)).collect();
+        assert_eq!(targets, vec![("Discord", true), ("S3", false)]);
+
+        // Only the upload that never happened is retried.
+        let queued = PendingUploads::load_from(&pending_path);
+        assert_eq!(queued.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_marker_of_live_run_is_skipped() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("backup_prod_20240101_000000.zip");
+        fs::write(&archive, b"zip").unwrap();
+        let live = PendingArchive::create(&archive, record(ArchiveStage::Compressing, Vec::new())).unwrap();
+        let app_state = AppState::new(String::new(), String::new());
+
+        let notices = settle_markers(dir.path(), &[], &dir.path().join("pending.json"), &app_state).await;
+        assert!(notices.is_empty());
+        assert!(archive.exists() && marker_path(&archive).exists());
+        live.clear();
+    }
+
+    #[tokio::test]
+    async fn test_unreadable_marker_is_reported_and_kept() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("backup_prod_20240101_000000.zip");
+        fs::write(&archive, b"zip").unwrap();
+        fs::write(marker_path(&archive), b"{ not json").unwrap();
+        let app_state = AppState::new(String::new(), String::new());
+
+        let notices = settle_markers(dir.path(), &[], &dir.path().join("pending.json"), &app_state).await;
+        assert_eq!(notices.len(), 1);
+        assert!(notices[0].contains("unreadable backup marker"));
+        assert!(archive.exists() && marker_path(&archive).exists());
+    }
+}
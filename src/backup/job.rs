@@ -1,15 +1,34 @@
-use crate::backup::compression::{calculate_sha256, compress_multiple_to_zip_silent};
-use crate::config::{AppConfig, DatabaseConfig};
-use crate::database::create_driver;
+use crate::backup::change_tracker::ChangeTracker;
+use crate::backup::compression::{calculate_sha256, compress_multiple_to_zip_with_level, split_archive, verify_archive};
+use crate::backup::inventory::{ArchiveStage, PendingArchive, PendingRecord, PENDING_EXTENSION};
+use crate::backup::queue::{CancelToken, JobQueue};
+use crate::backup::retention;
+use crate::config::{AppConfig, ArchiveMode, BackupJob, DatabaseConfig, DumpFormat};
+use crate::database::pattern::{is_pattern, resolve_databases};
+use crate::database::{
+    create_shared_driver, BinlogPosition, CheckpointCallback, DatabaseDriver, DumpCheckpoint, DumpProgress, DumpReport,
+    ProgressCallback,
+};
 use crate::error::Result;
-use crate::upload::{create_uploaders, BackupMetadata};
-use chrono::Utc;
-use std::fs::{self, File};
+use crate::log::events::{self, Event};
+use crate::log::{self as logging, Verbosity};
+use crate::upload::{create_uploaders, health, BackupMetadata, PendingUploads, UploadStatus};
+use chrono::{DateTime, Utc};
+use std::fs::{self, File, OpenOptions};
+use std::future::Future;
 use std::io::BufWriter;
-use std::path::PathBuf;
-use std::time::Instant;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tracing::{error, info, warn};
 
+pub const COMBINED_CONNECTION_NAME: &str = "combined";
+
+const DUMP_ATTEMPTS: u64 = 3;
+const DUMP_RETRY_DELAY_SECS: u64 = 5;
+/// Extension of the file listing the tables a partial dump has completed.
+pub const CHECKPOINT_EXTENSION: &str = "checkpoint";
+
 #[derive(Debug)]
 pub struct BackupResult {
 
@@ -28,35 +47,264 @@ pub struct BackupResult {
     pub error: Option<String>,
 
     pub db_errors: Vec<(String, String)>,
+
+    pub tags: Vec<String>,
+
+    pub skipped: Vec<String>,
+
+    pub db_warnings: Vec<(String, String)>,
+
+    pub uploads: Vec<UploadStatus>,
+
+    /// Marker of the archive, to clear once this result is in the history.
+    pub pending: Option<PendingArchive>,
+
+    /// Change markers of the archived databases, saved once the run has finished.
+    change_markers: Vec<(String, String)>,
+}
+
+impl BackupResult {
+    /// One-line summary for notifications and logs, e.g. `[OK] prod: 5 DBs, 14.2MB in 23s`.
+    pub fn summary_line(&self) -> String {
+        let failed = match self.db_errors.len() {
+            0 => String::new(),
+            count => format!(
+                " ({} DB{} failed: {})",
+                count,
+                if count == 1 { "" } else { "s" },
+                self.db_errors.iter().map(|(db, _)| db.as_str()).collect::<Vec<_>>().join(", ")
+            ),
+        };
+        if !self.success {
+            return format!(
+                "[FAIL] {}: {}{} after {}s",
+                self.connection_name,
+                self.error.as_deref().unwrap_or("Unknown error"),
+                failed,
+                self.duration_secs
+            );
+        }
+        if self.file_path.is_none() {
+            return format!("[SKIP] {}: {} DBs unchanged", self.connection_name, self.skipped.len());
+        }
+        format!(
+            "[{}] {}: {} DBs, {:.1}MB in {}s{}",
+            if self.db_errors.is_empty() { "OK" } else { "PARTIAL" },
+            self.connection_name,
+            self.databases.len(),
+            self.file_size.unwrap_or(0) as f64 / 1024.0 / 1024.0,
+            self.duration_secs,
+            failed
+        )
+    }
+
+    fn failure(
+        connection_name: &str,
+        databases: Vec<String>,
+        start: Instant,
+        error: String,
+        db_errors: Vec<(String, String)>,
+        tags: &[String],
+    ) -> Self {
+        Self {
+            connection_name: connection_name.to_string(),
+            databases,
+            success: false,
+            file_path: None,
+            file_size: None,
+            duration_secs: start.elapsed().as_secs(),
+            error: Some(error),
+            db_errors,
+            tags: tags.to_vec(),
+            skipped: Vec::new(),
+            db_warnings: Vec::new(),
+            uploads: Vec::new(),
+            pending: None,
+            change_markers: Vec::new(),
+        }
+    }
+
+    fn unchanged(connection_name: &str, start: Instant, skipped: Vec<String>, tags: &[String]) -> Self {
+        Self {
+            connection_name: connection_name.to_string(),
+            databases: Vec::new(),
+            success: true,
+            file_path: None,
+            file_size: None,
+            duration_secs: start.elapsed().as_secs(),
+            error: None,
+            db_errors: Vec::new(),
+            tags: tags.to_vec(),
+            skipped,
+            db_warnings: Vec::new(),
+            uploads: Vec::new(),
+            pending: None,
+            change_markers: Vec::new(),
+        }
+    }
+}
+
+struct DumpOutcome {
+    sql_files: Vec<(PathBuf, String)>,
+    successful_dbs: Vec<String>,
+    db_errors: Vec<(String, String)>,
+    skipped: Vec<String>,
+    db_warnings: Vec<(String, String)>,
+    binlog_positions: Vec<(String, BinlogPosition)>,
+    /// New change markers, keyed by [`ChangeTracker::key`].
+    change_markers: Vec<(String, String)>,
 }
 
 pub async fn execute_job_backup(
     config: &AppConfig,
     db_config: &DatabaseConfig,
-    databases: &[String],
-) -> BackupResult {
-    execute_job_backup_internal(config, db_config, databases, false).await
+    job: &BackupJob,
+    cancel: Option<CancelToken>,
+) -> Vec<BackupResult> {
+    execute_job_backup_internal(config, db_config, job, logging::verbosity(), None, cancel).await
 }
 
-pub async fn execute_job_backup_silent(
+pub async fn execute_job_backup_with_progress(
     config: &AppConfig,
     db_config: &DatabaseConfig,
-    databases: &[String],
-) -> BackupResult {
-    execute_job_backup_internal(config, db_config, databases, true).await
+    job: &BackupJob,
+    progress: ProgressCallback,
+    cancel: Option<CancelToken>,
+) -> Vec<BackupResult> {
+    execute_job_backup_internal(config, db_config, job, Verbosity::Quiet, Some(progress), cancel).await
 }
 
 async fn execute_job_backup_internal(
     config: &AppConfig,
     db_config: &DatabaseConfig,
-    databases: &[String],
-    silent: bool,
-) -> BackupResult {
+    job: &BackupJob,
+    verbosity: Verbosity,
+    progress: Option<ProgressCallback>,
+    cancel: Option<CancelToken>,
+) -> Vec<BackupResult> {
+    events::emit(Event::BackupStarted {
+        connection: db_config.name.clone(),
+        databases: job.databases.clone(),
+    });
     let start = Instant::now();
     let timestamp = Utc::now();
+    let backup = run_job_backup(config, db_config, job, timestamp, verbosity, progress);
+    let results = match run_limited(backup, config.job_timeout(job), cancel.as_ref()).await {
+        Ok(results) => {
+            commit_change_markers(&results);
+            results
+        }
+        Err(e) => {
+            let timestamp_str = timestamp.format("%Y%m%d_%H%M%S").to_string();
+            remove_partial_dumps(&config.local_backup_dir.join(&db_config.name), &timestamp_str);
+            vec![BackupResult::failure(&db_config.name, job.databases.clone(), start, e, vec![], &job.tags)]
+        }
+    };
+    for result in &results {
+        emit_result_event(result);
+    }
+    results
+}
+
+/// Runs `backup` under the time limit and cancel token, returning the error to report
+/// when either cuts it short.
+async fn run_limited<T>(
+    backup: impl Future<Output = T>,
+    limit: Option<Duration>,
+    cancel: Option<&CancelToken>,
+) -> std::result::Result<T, String> {
+    let limited = async {
+        match limit {
+            Some(limit) => tokio::time::timeout(limit, backup)
+                .await
+                .map_err(|_| format!("Backup timed out after {} seconds", limit.as_secs())),
+            None => Ok(backup.await),
+        }
+    };
+    match cancel {
+        Some(token) => tokio::select! {
+            outcome = limited => outcome,
+            _ = token.cancelled() => Err("Backup cancelled".to_string()),
+        },
+        None => limited.await,
+    }
+}
+
+/// Removes the dump files a timed-out or cancelled run left behind; they are only deleted
+/// after archiving, which the run never reached. Archives it had started are removed with
+/// their markers, since the run is reported as failed.
+fn remove_partial_dumps(backup_dir: &Path, timestamp_str: &str) {
+    let suffixes: Vec<String> = [DumpFormat::Sql, DumpFormat::Ndjson]
+        .iter()
+        .flat_map(|format| {
+            let suffix = format!("_{}.{}", timestamp_str, format.extension());
+            [format!("{}.{}", suffix, CHECKPOINT_EXTENSION), suffix]
+        })
+        .collect();
+    let marker_suffix = format!(".zip.{}", PENDING_EXTENSION);
+    let Ok(entries) = fs::read_dir(backup_dir) else {
+        return;
+    };
+    for path in entries.flatten().map(|e| e.path()) {
+        let Some(name) = path.file_name().map(|n| n.to_string_lossy().to_string()) else {
+            continue;
+        };
+        if name.ends_with(&marker_suffix) && name.contains(&format!("_{}", timestamp_str)) {
+            let _ = fs::remove_file(path.with_extension(""));
+        } else if !suffixes.iter().any(|suffix| name.ends_with(suffix)) {
+            continue;
+        }
+        if let Err(e) = fs::remove_file(&path) {
+            warn!("Failed to remove partial dump {}: {}", path.display(), e);
+        }
+    }
+}
+
+/// Saves the change markers of successful results. Only called once the run can no
+/// longer time out or be cancelled, so a database is only marked when its archive exists.
+fn commit_change_markers(results: &[BackupResult]) {
+    let markers: Vec<(String, String)> = results
+        .iter()
+        .filter(|r| r.success)
+        .flat_map(|r| r.change_markers.iter().cloned())
+        .collect();
+    if let Err(e) = ChangeTracker::commit(&markers) {
+        warn!("Failed to save change state: {}", e);
+    }
+}
+
+fn emit_result_event(result: &BackupResult) {
+    if result.success {
+        events::emit(Event::BackupCompleted {
+            connection: result.connection_name.clone(),
+            databases: result.databases.clone(),
+            file: result.file_path.clone(),
+            size: result.file_size.unwrap_or(0),
+            duration_secs: result.duration_secs,
+            uploads: result.uploads.clone(),
+        });
+    } else {
+        events::emit(Event::BackupFailed {
+            connection: result.connection_name.clone(),
+            error: result.error.clone().unwrap_or_default(),
+            duration_secs: result.duration_secs,
+        });
+    }
+}
+
+async fn run_job_backup(
+    config: &AppConfig,
+    db_config: &DatabaseConfig,
+    job: &BackupJob,
+    timestamp: DateTime<Utc>,
+    verbosity: Verbosity,
+    progress: Option<ProgressCallback>,
+) -> Vec<BackupResult> {
+    let databases = &job.databases;
+    let start = Instant::now();
     let timestamp_str = timestamp.format("%Y%m%d_%H%M%S").to_string();
-    
-    if !silent {
+
+    if !verbosity.is_quiet() {
         info!(
             "Starting combined backup for {} databases on connection '{}'",
             databases.len(),
@@ -64,135 +312,604 @@ async fn execute_job_backup_internal(
         );
     }
     let backup_dir = config.local_backup_dir.join(&db_config.name);
-    
+
     if let Err(e) = fs::create_dir_all(&backup_dir) {
-        return BackupResult {
-            connection_name: db_config.name.clone(),
-            databases: databases.to_vec(),
-            success: false,
-            file_path: None,
-            file_size: None,
-            duration_secs: start.elapsed().as_secs(),
-            error: Some(format!("Failed to create backup directory: {}", e)),
-            db_errors: vec![],
+        return vec![BackupResult::failure(
+            &db_config.name,
+            databases.to_vec(),
+            start,
+            format!("Failed to create backup directory: {}", e),
+            vec![],
+            &job.tags,
+        )];
+    }
+    if let Some(max_total_bytes) = db_config.max_total_bytes {
+        if let Err(e) = retention::enforce_quota(&backup_dir, max_total_bytes, db_config.keep_last) {
+            return vec![BackupResult::failure(&db_config.name, databases.to_vec(), start, e, vec![], &job.tags)];
+        }
+    }
+    let outcome = match dump_databases(db_config, job, &backup_dir, &timestamp_str, verbosity, progress).await {
+        Ok(o) => o,
+        Err(e) => {
+            return vec![BackupResult::failure(&db_config.name, databases.to_vec(), start, e, vec![], &job.tags)];
+        }
+    };
+
+    match job.archive_mode {
+        ArchiveMode::Combined => vec![
+            archive_combined(config, db_config, job, &backup_dir, outcome, timestamp, start, verbosity).await,
+        ],
+        ArchiveMode::PerDatabase => {
+            archive_per_database(config, db_config, job, &backup_dir, outcome, timestamp, start, verbosity).await
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn archive_combined(
+    config: &AppConfig,
+    db_config: &DatabaseConfig,
+    job: &BackupJob,
+    backup_dir: &Path,
+    outcome: DumpOutcome,
+    timestamp: DateTime<Utc>,
+    start: Instant,
+    verbosity: Verbosity,
+) -> BackupResult {
+    let databases = &job.databases;
+    let timestamp_str = timestamp.format("%Y%m%d_%H%M%S").to_string();
+    if outcome.sql_files.is_empty() && outcome.db_errors.is_empty() && !outcome.skipped.is_empty() {
+        if !verbosity.is_quiet() {
+            info!("All databases on '{}' unchanged since last backup, skipping", db_config.name);
+        }
+        return BackupResult::unchanged(&db_config.name, start, outcome.skipped, &job.tags);
+    }
+    if outcome.sql_files.is_empty() {
+        return BackupResult::failure(
+            &db_config.name,
+            databases.to_vec(),
+            start,
+            "No databases were successfully dumped".to_string(),
+            outcome.db_errors,
+            &job.tags,
+        );
+    }
+    let zip_path = match reserve_archive_path(backup_dir, &format!("backup_{}_{}", db_config.name, timestamp_str)) {
+        Ok(path) => path,
+        Err(e) => {
+            for (sql_path, _) in &outcome.sql_files {
+                let _ = fs::remove_file(sql_path);
+            }
+            return BackupResult::failure(
+                &db_config.name,
+                databases.to_vec(),
+                start,
+                format!("Failed to create archive: {}", e),
+                outcome.db_errors,
+                &job.tags,
+            );
+        }
+    };
+
+    archive_and_upload(
+        config,
+        &db_config.name,
+        outcome,
+        zip_path,
+        timestamp,
+        start,
+        &job.tags,
+        verbosity,
+    )
+    .await
+}
+
+/// Archives and uploads each dumped database on its own, so every database gets
+/// its own zip and result. Failed and unchanged databases get a result as well.
+#[allow(clippy::too_many_arguments)]
+async fn archive_per_database(
+    config: &AppConfig,
+    db_config: &DatabaseConfig,
+    job: &BackupJob,
+    backup_dir: &Path,
+    outcome: DumpOutcome,
+    timestamp: DateTime<Utc>,
+    start: Instant,
+    verbosity: Verbosity,
+) -> Vec<BackupResult> {
+    let timestamp_str = timestamp.format("%Y%m%d_%H%M%S").to_string();
+    let DumpOutcome {
+        sql_files,
+        successful_dbs,
+        db_errors,
+        skipped,
+        db_warnings,
+        binlog_positions,
+        change_markers,
+    } = outcome;
+
+    let mut results = Vec::new();
+    for (sql_file, db_name) in sql_files.into_iter().zip(successful_dbs) {
+        let zip_path = match reserve_archive_path(
+            backup_dir,
+            &format!("backup_{}_{}_{}", db_config.name, db_name, timestamp_str),
+        ) {
+            Ok(path) => path,
+            Err(e) => {
+                let _ = fs::remove_file(&sql_file.0);
+                results.push(BackupResult::failure(
+                    &db_config.name,
+                    vec![db_name],
+                    start,
+                    format!("Failed to create archive: {}", e),
+                    vec![],
+                    &job.tags,
+                ));
+                continue;
+            }
         };
+        let single = DumpOutcome {
+            sql_files: vec![sql_file],
+            successful_dbs: vec![db_name.clone()],
+            db_errors: Vec::new(),
+            skipped: Vec::new(),
+            db_warnings: db_warnings.iter().filter(|(db, _)| *db == db_name).cloned().collect(),
+            binlog_positions: binlog_positions.iter().filter(|(db, _)| *db == db_name).cloned().collect(),
+            change_markers: change_markers
+                .iter()
+                .filter(|(key, _)| *key == ChangeTracker::key(&db_config.name, &db_name))
+                .cloned()
+                .collect(),
+        };
+        results.push(
+            archive_and_upload(config, &db_config.name, single, zip_path, timestamp, start, &job.tags, verbosity).await,
+        );
     }
-    let driver = match create_driver(db_config) {
-        Ok(d) => d,
+
+    for (db_name, error) in db_errors {
+        results.push(BackupResult::failure(
+            &db_config.name,
+            vec![db_name.clone()],
+            start,
+            error.clone(),
+            vec![(db_name, error)],
+            &job.tags,
+        ));
+    }
+    for db_name in skipped {
+        results.push(BackupResult::unchanged(&db_config.name, start, vec![db_name], &job.tags));
+    }
+
+    if results.is_empty() {
+        results.push(BackupResult::failure(
+            &db_config.name,
+            job.databases.to_vec(),
+            start,
+            "No databases were successfully dumped".to_string(),
+            vec![],
+            &job.tags,
+        ));
+    }
+    results
+}
+
+/// The archive as one file, or split into volumes plus their manifest when it is larger
+/// than `upload.split_size_mb`. Each file gets its own copy of the metadata.
+fn files_to_upload(config: &AppConfig, zip_path: &Path, metadata: &BackupMetadata) -> Vec<(PathBuf, BackupMetadata)> {
+    let whole = vec![(zip_path.to_path_buf(), metadata.clone())];
+    let Some(volume_size) = config.upload.split_size_mb.filter(|mb| *mb > 0).map(|mb| mb * 1024 * 1024) else {
+        return whole;
+    };
+    if metadata.file_size <= volume_size {
+        return whole;
+    }
+
+    let split = match split_archive(zip_path, volume_size) {
+        Ok(split) => split,
         Err(e) => {
-            return BackupResult {
-                connection_name: db_config.name.clone(),
-                databases: databases.to_vec(),
-                success: false,
-                file_path: None,
-                file_size: None,
-                duration_secs: start.elapsed().as_secs(),
-                error: Some(format!("Failed to create database driver: {}", e)),
-                db_errors: vec![],
-            };
+            warn!("Failed to split {} into volumes, uploading it whole: {}", zip_path.display(), e);
+            return whole;
         }
     };
+    let manifest_hash = calculate_sha256(&split.manifest).ok();
+    split
+        .parts
+        .into_iter()
+        .map(|(path, hash)| (path, Some(hash)))
+        .chain(std::iter::once((split.manifest, manifest_hash)))
+        .map(|(path, file_hash)| {
+            let file_metadata = BackupMetadata {
+                file_size: fs::metadata(&path).map(|m| m.len()).unwrap_or(0),
+                file_hash,
+                file_path: path.to_string_lossy().to_string(),
+                ..metadata.clone()
+            };
+            (path, file_metadata)
+        })
+        .collect()
+}
+
+/// Claims `{stem}.zip` in `dir`, or `{stem}_2.zip`, `{stem}_3.zip`, ... when runs
+/// within the same second already produced an archive with that name. The file is
+/// created empty so a concurrent run can't pick the same name.
+fn reserve_archive_path(dir: &Path, stem: &str) -> std::io::Result<PathBuf> {
+    for n in 1.. {
+        let name = if n == 1 {
+            format!("{}.zip", stem)
+        } else {
+            format!("{}_{}.zip", stem, n)
+        };
+        let path = dir.join(name);
+        match fs::OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(_) => return Ok(path),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    unreachable!()
+}
+
+async fn dump_databases(
+    db_config: &DatabaseConfig,
+    job: &BackupJob,
+    backup_dir: &Path,
+    timestamp_str: &str,
+    verbosity: Verbosity,
+    progress: Option<ProgressCallback>,
+) -> std::result::Result<DumpOutcome, String> {
+    let mut driver = create_shared_driver(db_config)
+        .map_err(|e| format!("Failed to create database driver: {}", e))?;
+    driver.set_pk_ranges(job.pk_ranges.clone());
+    driver.set_dump_format(job.dump_format);
+
     let mut sql_files: Vec<(PathBuf, String)> = Vec::new();
     let mut db_errors: Vec<(String, String)> = Vec::new();
     let mut successful_dbs: Vec<String> = Vec::new();
+    let mut skipped: Vec<String> = Vec::new();
+    let mut db_warnings: Vec<(String, String)> = Vec::new();
+    let mut binlog_positions: Vec<(String, BinlogPosition)> = Vec::new();
+    let mut change_markers: Vec<(String, String)> = Vec::new();
+    let tracker = job.skip_unchanged.then(ChangeTracker::load);
+
+    let exclude: Vec<String> = db_config
+        .exclude_databases
+        .iter()
+        .chain(&job.exclude_databases)
+        .cloned()
+        .collect();
+    let databases = if job.databases.iter().any(|db| is_pattern(db)) || !exclude.is_empty() {
+        let available = driver
+            .list_databases()
+            .await
+            .map_err(|e| format!("Failed to list databases: {}", e))?;
+        resolve_databases(&job.databases, &available, &exclude)
+    } else {
+        job.databases.clone()
+    };
 
-    for db_name in databases {
-        if !silent {
+    let database_count = databases.len();
+    for (index, db_name) in databases.iter().enumerate() {
+        let marker = match &tracker {
+            Some(_) => driver.change_marker(db_name).await.unwrap_or_else(|e| {
+                warn!("Could not check {} for changes: {}", db_name, e);
+                None
+            }),
+            None => None,
+        };
+        if let (Some(t), Some(m)) = (&tracker, &marker) {
+            if t.is_unchanged(&db_config.name, db_name, m) {
+                if !verbosity.is_quiet() {
+                    info!("Skipping {} (unchanged since last backup)", db_name);
+                }
+                skipped.push(db_name.clone());
+                continue;
+            }
+        }
+
+        if !verbosity.is_quiet() {
             info!("Dumping database: {}", db_name);
         }
-        
-        let sql_filename = format!("{}_{}.sql", db_name, timestamp_str);
+
+        let sql_filename = format!("{}_{}.{}", db_name, timestamp_str, job.dump_format.extension());
         let sql_path = backup_dir.join(&sql_filename);
-        let sql_file = match File::create(&sql_path) {
-            Ok(f) => f,
+        // The driver only sees one database; tag its progress with the position in the job.
+        let db_progress = progress.clone().map(|callback| -> ProgressCallback {
+            Arc::new(move |mut p: DumpProgress| {
+                p.database_index = index + 1;
+                p.database_count = database_count;
+                callback(p)
+            })
+        });
+        // A resumed dump mixes reads from before and after the retry, which would
+        // break the consistent snapshot a recorded binlog position relies on.
+        let resumable = !db_config.record_binlog_position;
+        let retry_delay = Duration::from_secs(DUMP_RETRY_DELAY_SECS);
+        match dump_with_retry(driver.as_ref(), db_name, &sql_path, resumable, verbosity, db_progress, retry_delay).await {
+            Ok(report) => {
+                db_warnings.extend(report.warnings.into_iter().map(|w| (db_name.clone(), w)));
+                if let Some(pos) = report.binlog_position {
+                    binlog_positions.push((db_name.clone(), pos));
+                }
+            }
             Err(e) => {
-                if !silent {
-                    error!("Failed to create SQL file for {}: {}", db_name, e);
+                if !verbosity.is_quiet() {
+                    error!("Failed to dump database {}: {}", db_name, e);
                 }
-                db_errors.push((db_name.clone(), format!("Failed to create file: {}", e)));
+                let _ = fs::remove_file(&sql_path);
+                db_errors.push((db_name.clone(), format!("Failed to dump: {}", e)));
                 continue;
             }
-        };
-        
-        let writer = BufWriter::new(sql_file);
-        if let Err(e) = driver.dump_database_silent(db_name, Box::new(writer), silent).await {
-            if !silent {
-                error!("Failed to dump database {}: {}", db_name, e);
-            }
-            let _ = fs::remove_file(&sql_path);
-            db_errors.push((db_name.clone(), format!("Failed to dump: {}", e)));
-            continue;
         }
-        
-        if !silent {
+
+        if !verbosity.is_quiet() {
             info!("Successfully dumped: {}", db_name);
         }
         sql_files.push((sql_path, sql_filename));
         successful_dbs.push(db_name.clone());
+        if let Some(m) = marker {
+            change_markers.push((ChangeTracker::key(&db_config.name, db_name), m));
+        }
     }
-    if sql_files.is_empty() {
-        return BackupResult {
-            connection_name: db_config.name.clone(),
-            databases: databases.to_vec(),
-            success: false,
-            file_path: None,
-            file_size: None,
-            duration_secs: start.elapsed().as_secs(),
-            error: Some("No databases were successfully dumped".to_string()),
-            db_errors,
+
+    if let Err(e) = driver.close().await {
+        warn!("Failed to close connection to '{}': {}", db_config.name, e);
+    }
+
+    Ok(DumpOutcome {
+        sql_files,
+        successful_dbs,
+        db_errors,
+        skipped,
+        db_warnings,
+        binlog_positions,
+        change_markers,
+    })
+}
+
+/// Dumps `db_name` into `sql_path`, retrying after `retry_delay` times the attempt number
+/// when the failure is transient (connection refused, timeout). Other errors are returned
+/// straight away.
+///
+/// When `resumable`, completed tables are recorded in `{sql_path}.checkpoint` and a retry
+/// appends the remaining tables to the partial dump instead of starting over.
+async fn dump_with_retry(
+    driver: &dyn DatabaseDriver,
+    db_name: &str,
+    sql_path: &Path,
+    resumable: bool,
+    verbosity: Verbosity,
+    progress: Option<ProgressCallback>,
+    retry_delay: Duration,
+) -> Result<DumpReport> {
+    let checkpoint_path = checkpoint_path(sql_path);
+    let on_checkpoint = resumable.then(|| -> CheckpointCallback {
+        let path = checkpoint_path.clone();
+        Arc::new(move |checkpoint: &DumpCheckpoint| {
+            let written = serde_json::to_vec(checkpoint)
+                .map_err(|e| e.to_string())
+                .and_then(|json| fs::write(&path, json).map_err(|e| e.to_string()));
+            if let Err(e) = written {
+                warn!("Failed to write dump checkpoint {}: {}", path.display(), e);
+            }
+        })
+    });
+
+    let mut attempt = 1;
+    let result = loop {
+        let resume = if resumable { open_checkpoint(sql_path, &checkpoint_path) } else { None };
+        let file = match &resume {
+            Some(_) => OpenOptions::new().append(true).open(sql_path)?,
+            None => File::create(sql_path)?,
         };
+        if let Some(checkpoint) = &resume {
+            info!("Resuming dump of {} after {} table(s)", db_name, checkpoint.tables.len());
+        }
+        let writer = BufWriter::new(file);
+        match driver
+            .dump_database_resumable(db_name, Box::new(writer), verbosity, progress.clone(), resume, on_checkpoint.clone())
+            .await
+        {
+            Err(e) if e.is_transient() && attempt < DUMP_ATTEMPTS => {
+                let delay = retry_delay * attempt as u32;
+                warn!("Dump of {} failed ({}), retrying in {}s", db_name, e, delay.as_secs());
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            result => break result,
+        }
+    };
+    let _ = fs::remove_file(&checkpoint_path);
+    result
+}
+
+fn checkpoint_path(sql_path: &Path) -> PathBuf {
+    let mut name = sql_path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{}", CHECKPOINT_EXTENSION));
+    sql_path.with_file_name(name)
+}
+
+/// The checkpoint of a partial dump, with the dump cut back to the end of the last
+/// completed table. `None` (start over) when there is no usable checkpoint.
+fn open_checkpoint(sql_path: &Path, checkpoint_path: &Path) -> Option<DumpCheckpoint> {
+    let checkpoint: DumpCheckpoint = serde_json::from_slice(&fs::read(checkpoint_path).ok()?).ok()?;
+    let file = OpenOptions::new().write(true).open(sql_path).ok()?;
+    if file.metadata().ok()?.len() < checkpoint.offset {
+        return None;
     }
-    let zip_filename = format!("backup_{}_{}.zip", db_config.name, timestamp_str);
-    let zip_path = backup_dir.join(&zip_filename);
-    
-    if !silent {
+    file.set_len(checkpoint.offset).ok()?;
+    Some(checkpoint)
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn archive_and_upload(
+    config: &AppConfig,
+    connection_name: &str,
+    outcome: DumpOutcome,
+    zip_path: PathBuf,
+    timestamp: DateTime<Utc>,
+    start: Instant,
+    tags: &[String],
+    verbosity: Verbosity,
+) -> BackupResult {
+    let DumpOutcome {
+        sql_files,
+        successful_dbs,
+        db_errors,
+        skipped,
+        db_warnings,
+        binlog_positions,
+        change_markers,
+    } = outcome;
+
+    if !verbosity.is_quiet() {
         info!("Creating combined archive with {} databases", sql_files.len());
     }
-    
-    if let Err(e) = compress_multiple_to_zip_silent(&sql_files, &zip_path, silent) {
+
+    // Written before compressing, so a run that dies part-way leaves a trace for
+    // `inventory::reconcile` instead of an archive nobody knows about.
+    let record = PendingRecord {
+        stage: ArchiveStage::Compressing,
+        connection_name: connection_name.to_string(),
+        databases: successful_dbs.clone(),
+        timestamp,
+        file_size: 0,
+        file_hash: None,
+        duration_secs: 0,
+        tags: tags.to_vec(),
+        uploads: Vec::new(),
+    };
+    let mut pending = match PendingArchive::create(&zip_path, record) {
+        Ok(pending) => Some(pending),
+        Err(e) => {
+            warn!("Failed to write backup marker for {}: {}", zip_path.display(), e);
+            None
+        }
+    };
+
+    // Embedded as the zip comment, so the archive describes itself. Size and hash are
+    // left out: they are those of the archive the comment is part of.
+    let mut metadata = BackupMetadata {
+        databases: successful_dbs.clone(),
+        connection_name: connection_name.to_string(),
+        timestamp,
+        file_size: 0,
+        file_hash: None,
+        duration_secs: start.elapsed().as_secs(),
+        file_path: zip_path.file_name().unwrap_or_default().to_string_lossy().to_string(),
+        tags: tags.to_vec(),
+        binlog_positions: binlog_positions.into_iter().collect(),
+    };
+    let comment = serde_json::to_string(&metadata).ok();
+    let compressed = compress_multiple_to_zip_with_level(
+        &sql_files,
+        &zip_path,
+        verbosity.is_quiet(),
+        config.compression_level,
+        comment.as_deref(),
+    )
+    .and_then(|hash| verify_archive(&zip_path, config.verify_archive_hashes).map(|_| hash));
+    let file_hash = match compressed {
+        Ok(hash) => Some(hash),
+        Err(e) => {
+            for (sql_path, _) in &sql_files {
+                let _ = fs::remove_file(sql_path);
+            }
+            let _ = fs::remove_file(&zip_path);
+            if let Some(pending) = &pending {
+                pending.clear();
+            }
+            return BackupResult::failure(
+                connection_name,
+                successful_dbs,
+                start,
+                format!("Failed to create archive: {}", e),
+                db_errors,
+                tags,
+            );
+        }
+    };
+    if !config.keep_uncompressed {
         for (sql_path, _) in &sql_files {
             let _ = fs::remove_file(sql_path);
         }
-        return BackupResult {
-            connection_name: db_config.name.clone(),
-            databases: successful_dbs,
-            success: false,
-            file_path: None,
-            file_size: None,
-            duration_secs: start.elapsed().as_secs(),
-            error: Some(format!("Failed to create archive: {}", e)),
-            db_errors,
-        };
-    }
-    for (sql_path, _) in &sql_files {
-        let _ = fs::remove_file(sql_path);
     }
     let file_size = fs::metadata(&zip_path).map(|m| m.len()).unwrap_or(0);
-    let file_hash = calculate_sha256(&zip_path).ok();
 
     let duration_secs = start.elapsed().as_secs();
-    let metadata = BackupMetadata {
-        databases: successful_dbs.clone(),
-        connection_name: db_config.name.clone(),
-        timestamp,
-        file_size,
-        file_hash,
-        duration_secs,
-        file_path: zip_path.to_string_lossy().to_string(),
-    };
+    metadata.file_size = file_size;
+    metadata.file_hash = file_hash;
+    metadata.duration_secs = duration_secs;
+    metadata.file_path = zip_path.to_string_lossy().to_string();
+    if config.metadata_sidecar {
+        let sidecar = metadata_sidecar_path(&zip_path);
+        let written = serde_json::to_vec_pretty(&metadata)
+            .map_err(|e| e.to_string())
+            .and_then(|json| fs::write(&sidecar, json).map_err(|e| e.to_string()));
+        if let Err(e) = written {
+            warn!("Failed to write metadata sidecar {}: {}", sidecar.display(), e);
+        }
+    }
+    update_marker(&mut pending, |record| {
+        record.stage = ArchiveStage::Uploading;
+        record.file_size = file_size;
+        record.file_hash = metadata.file_hash.clone();
+        record.duration_secs = duration_secs;
+    });
     let uploaders = create_uploaders(&config.upload);
+    let upload_files = if uploaders.is_empty() {
+        Vec::new()
+    } else {
+        files_to_upload(config, &zip_path, &metadata)
+    };
+    let mut uploads = Vec::with_capacity(uploaders.len());
     for uploader in &uploaders {
-        if !silent {
+        if !verbosity.is_quiet() {
             info!("Uploading combined backup to {}", uploader.name());
         }
-        if let Err(e) = uploader.upload_silent(&metadata, &zip_path, silent).await {
-            if !silent {
-                error!("Failed to upload to {}: {}", uploader.name(), e);
+        let mut failure: Option<String> = None;
+        for (path, file_metadata) in &upload_files {
+            match uploader.upload_silent(file_metadata, path, verbosity).await {
+                Ok(_) => {
+                    events::emit(Event::UploadSucceeded {
+                        uploader: uploader.name().to_string(),
+                        file: file_metadata.file_path.clone(),
+                    });
+                }
+                Err(e) => {
+                    events::emit(Event::UploadFailed {
+                        uploader: uploader.name().to_string(),
+                        file: file_metadata.file_path.clone(),
+                        error: e.to_string(),
+                    });
+                    if !verbosity.is_quiet() {
+                        error!("Failed to upload to {}: {}", uploader.name(), e);
+                    }
+                    PendingUploads::enqueue(uploader.name(), file_metadata, &e.to_string());
+                    failure.get_or_insert(e.to_string());
+                }
             }
         }
+        match failure {
+            None => health::record_success(uploader.name()),
+            Some(_) => health::record_failure(&config.upload, uploader.name()),
+        }
+        let status = UploadStatus {
+            target: uploader.name().to_string(),
+            ok: failure.is_none(),
+            error: failure,
+        };
+        update_marker(&mut pending, |record| record.uploads.push(status.clone()));
+        uploads.push(status);
+    }
+    update_marker(&mut pending, |record| record.stage = ArchiveStage::Uploaded);
+    // Volumes only exist for transport; keep them while a pending retry still needs them.
+    if upload_files.len() > 1 && uploads.iter().all(|u| u.ok) {
+        for (path, _) in &upload_files {
+            let _ = fs::remove_file(path);
+        }
     }
 
-    if !silent {
+    if !verbosity.is_quiet() {
         info!(
             "Combined backup completed: {} databases, {} seconds, {:.2} MB",
             successful_dbs.len(),
@@ -202,7 +919,7 @@ async fn execute_job_backup_internal(
     }
 
     BackupResult {
-        connection_name: db_config.name.clone(),
+        connection_name: connection_name.to_string(),
         databases: successful_dbs,
         success: true,
         file_path: Some(zip_path),
@@ -210,13 +927,148 @@ async fn execute_job_backup_internal(
         duration_secs,
         error: None,
         db_errors,
+        tags: tags.to_vec(),
+        skipped,
+        db_warnings,
+        uploads,
+        pending,
+        change_markers,
+    }
+}
+
+/// `{archive}.json` next to the archive, written when `metadata_sidecar` is set.
+pub fn metadata_sidecar_path(archive_path: &Path) -> PathBuf {
+    let mut name = archive_path.file_name().unwrap_or_default().to_os_string();
+    name.push(".json");
+    archive_path.with_file_name(name)
+}
+
+fn update_marker(pending: &mut Option<PendingArchive>, change: impl FnOnce(&mut PendingRecord)) {
+    if let Some(marker) = pending {
+        if let Err(e) = marker.update(change) {
+            warn!("Failed to update backup marker: {}", e);
+        }
     }
 }
 
-pub async fn execute_all_jobs(config: &AppConfig) -> Vec<BackupResult> {
+/// Runs every enabled job in priority order. All jobs are queued up front so they
+/// can be inspected and cancelled through `queue` while earlier ones run.
+pub async fn execute_all_jobs(config: &AppConfig, queue: &JobQueue) -> Vec<BackupResult> {
+    if config.combine_jobs {
+        let job = queue.enqueue(COMBINED_CONNECTION_NAME, &combined_databases(config));
+        let run = |token| execute_combined_backup(config, logging::verbosity(), None, Some(token));
+        let Some(result) = queue.run(job, run, |r: &BackupResult| r.success).await else {
+            info!("Skipping cancelled combined backup");
+            return Vec::new();
+        };
+        return vec![result];
+    }
+
+    let mut queued = Vec::new();
+    for job in config.enabled_jobs_by_priority() {
+        match config.databases.iter().find(|d| d.name == job.db_config_name) {
+            Some(db_config) => queued.push((db_config, job, queue.enqueue(&job.db_config_name, &job.databases))),
+            None => warn!("Database config '{}' not found for job", job.db_config_name),
+        }
+    }
+
     let mut results = Vec::new();
+    for (db_config, job, queued_job) in queued {
+        let run = |token| execute_job_backup(config, db_config, job, Some(token));
+        let Some(job_results) = queue.run(queued_job, run, |r: &Vec<BackupResult>| all_succeeded(r)).await else {
+            info!("Skipping cancelled backup job for {}", job.db_config_name);
+            continue;
+        };
+        results.extend(job_results);
+    }
+
+    results
+}
 
-    for job in &config.backup_jobs {
+pub fn all_succeeded(results: &[BackupResult]) -> bool {
+    results.iter().all(|r| r.success)
+}
+
+/// Databases of every enabled job, as `connection/database`.
+pub fn combined_databases(config: &AppConfig) -> Vec<String> {
+    config
+        .enabled_jobs_by_priority()
+        .into_iter()
+        .flat_map(|j| j.databases.iter().map(move |db| format!("{}/{}", j.db_config_name, db)))
+        .collect()
+}
+
+fn combined_tags(config: &AppConfig) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for tag in config.enabled_jobs_by_priority().into_iter().flat_map(|j| &j.tags) {
+        if !tags.contains(tag) {
+            tags.push(tag.clone());
+        }
+    }
+    tags
+}
+
+/// Backs up every enabled job into one archive. The run is limited by the sum of the
+/// jobs' timeouts and can be cancelled like a single job.
+pub async fn execute_combined_backup(
+    config: &AppConfig,
+    verbosity: Verbosity,
+    progress: Option<ProgressCallback>,
+    cancel: Option<CancelToken>,
+) -> BackupResult {
+    let databases = combined_databases(config);
+    events::emit(Event::BackupStarted {
+        connection: COMBINED_CONNECTION_NAME.to_string(),
+        databases: databases.clone(),
+    });
+    let start = Instant::now();
+    let timestamp = Utc::now();
+    let backup = run_combined_backup(config, timestamp, verbosity, progress);
+    let result = match run_limited(backup, config.combined_timeout(), cancel.as_ref()).await {
+        Ok(result) => {
+            commit_change_markers(std::slice::from_ref(&result));
+            result
+        }
+        Err(e) => {
+            let timestamp_str = timestamp.format("%Y%m%d_%H%M%S").to_string();
+            for job in config.enabled_jobs_by_priority() {
+                remove_partial_dumps(&config.local_backup_dir.join(&job.db_config_name), &timestamp_str);
+            }
+            remove_partial_dumps(&config.local_backup_dir, &timestamp_str);
+            BackupResult::failure(COMBINED_CONNECTION_NAME, databases, start, e, vec![], &combined_tags(config))
+        }
+    };
+    emit_result_event(&result);
+    result
+}
+
+async fn run_combined_backup(
+    config: &AppConfig,
+    timestamp: DateTime<Utc>,
+    verbosity: Verbosity,
+    progress: Option<ProgressCallback>,
+) -> BackupResult {
+    let start = Instant::now();
+    let timestamp_str = timestamp.format("%Y%m%d_%H%M%S").to_string();
+    let tags = combined_tags(config);
+    let mut combined = DumpOutcome {
+        sql_files: Vec::new(),
+        successful_dbs: Vec::new(),
+        db_errors: Vec::new(),
+        skipped: Vec::new(),
+        db_warnings: Vec::new(),
+        binlog_positions: Vec::new(),
+        change_markers: Vec::new(),
+    };
+
+    if !verbosity.is_quiet() {
+        info!(
+            "Starting full backup of {} jobs into a single archive",
+            config.backup_jobs.iter().filter(|j| j.enabled).count()
+        );
+    }
+
+    for job in config.enabled_jobs_by_priority() {
         let db_config = match config.databases.iter().find(|d| d.name == job.db_config_name) {
             Some(c) => c,
             None => {
@@ -224,9 +1076,346 @@ pub async fn execute_all_jobs(config: &AppConfig) -> Vec<BackupResult> {
                 continue;
             }
         };
-        let result = execute_job_backup(config, db_config, &job.databases).await;
-        results.push(result);
+
+        let backup_dir = config.local_backup_dir.join(&db_config.name);
+        if let Err(e) = fs::create_dir_all(&backup_dir) {
+            combined.db_errors.push((
+                db_config.name.clone(),
+                format!("Failed to create backup directory: {}", e),
+            ));
+            continue;
+        }
+        if let Some(max_total_bytes) = db_config.max_total_bytes {
+            if let Err(e) = retention::enforce_quota(&backup_dir, max_total_bytes, db_config.keep_last) {
+                combined.db_errors.push((db_config.name.clone(), e));
+                continue;
+            }
+        }
+
+        match dump_databases(db_config, job, &backup_dir, &timestamp_str, verbosity, progress.clone()).await {
+            Ok(outcome) => {
+                let prefix = |name: &str| format!("{}/{}", db_config.name, name);
+                combined.sql_files.extend(
+                    outcome
+                        .sql_files
+                        .into_iter()
+                        .map(|(path, entry)| (path, prefix(&entry))),
+                );
+                combined
+                    .successful_dbs
+                    .extend(outcome.successful_dbs.iter().map(|db| prefix(db)));
+                combined.db_errors.extend(
+                    outcome
+                        .db_errors
+                        .into_iter()
+                        .map(|(db, err)| (prefix(&db), err)),
+                );
+                combined
+                    .skipped
+                    .extend(outcome.skipped.iter().map(|db| prefix(db)));
+                combined.db_warnings.extend(
+                    outcome
+                        .db_warnings
+                        .into_iter()
+                        .map(|(db, warning)| (prefix(&db), warning)),
+                );
+                combined.binlog_positions.extend(
+                    outcome
+                        .binlog_positions
+                        .into_iter()
+                        .map(|(db, pos)| (prefix(&db), pos)),
+                );
+                combined.change_markers.extend(outcome.change_markers);
+            }
+            Err(e) => combined.db_errors.push((db_config.name.clone(), e)),
+        }
+    }
+
+    if combined.sql_files.is_empty() && combined.db_errors.is_empty() && !combined.skipped.is_empty() {
+        if !verbosity.is_quiet() {
+            info!("All databases unchanged since last backup, skipping full backup");
+        }
+        return BackupResult::unchanged(COMBINED_CONNECTION_NAME, start, combined.skipped, &tags);
+    }
+
+    if combined.sql_files.is_empty() {
+        return BackupResult::failure(
+            COMBINED_CONNECTION_NAME,
+            Vec::new(),
+            start,
+            "No databases were successfully dumped".to_string(),
+            combined.db_errors,
+            &tags,
+        );
+    }
+
+    let zip_path = match reserve_archive_path(&config.local_backup_dir, &format!("full_backup_{}", timestamp_str)) {
+        Ok(path) => path,
+        Err(e) => {
+            for (sql_path, _) in &combined.sql_files {
+                let _ = fs::remove_file(sql_path);
+            }
+            return BackupResult::failure(
+                COMBINED_CONNECTION_NAME,
+                combined.successful_dbs,
+                start,
+                format!("Failed to create archive: {}", e),
+                combined.db_errors,
+                &tags,
+            );
+        }
+    };
+
+    archive_and_upload(
+        config,
+        COMBINED_CONNECTION_NAME,
+        combined,
+        zip_path,
+        timestamp,
+        start,
+        &tags,
+        verbosity,
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::PkRange;
+    use crate::error::{BackupError, DatabaseErrorKind};
+    use async_trait::async_trait;
+    use std::collections::{BTreeMap, VecDeque};
+    use std::io::{BufRead, Write};
+    use std::sync::Mutex;
+
+    const HEADER: &str = "-- dump of app\n";
+    const TABLES: [&str; 4] = ["users", "orders", "items", "logs"];
+
+    /// Dumps [`TABLES`], failing with a timeout once a call has completed the number of
+    /// tables at the front of `failures`, after writing half a table.
+    struct FlakyDriver {
+        failures: Mutex<VecDeque<usize>>,
+    }
+
+    impl FlakyDriver {
+        fn new(failures: &[usize]) -> Self {
+            Self {
+                failures: Mutex::new(failures.iter().copied().collect()),
+            }
+        }
+    }
+
+    fn table_output(table: &str) -> String {
+        format!("INSERT INTO {} VALUES (1), (2);\n", table)
+    }
+
+    #[async_trait]
+    impl DatabaseDriver for FlakyDriver {
+        async fn test_connection(&self) -> Result<()> {
+            Ok(())
+        }
+        async fn list_databases(&self) -> Result<Vec<String>> {
+            Ok(vec!["app".to_string()])
+        }
+        async fn dump_database(&self, db_name: &str, writer: Box<dyn Write + Send>) -> Result<DumpReport> {
+            self.dump_database_resumable(db_name, writer, Verbosity::Quiet, None, None, None).await
+        }
+        async fn dump_database_silent(
+            &self,
+            db_name: &str,
+            writer: Box<dyn Write + Send>,
+            verbosity: Verbosity,
+            progress: Option<ProgressCallback>,
+        ) -> Result<DumpReport> {
+            self.dump_database_resumable(db_name, writer, verbosity, progress, None, None).await
+        }
+        async fn dump_database_resumable(
+            &self,
+            _db_name: &str,
+            mut writer: Box<dyn Write + Send>,
+            _verbosity: Verbosity,
+            _progress: Option<ProgressCallback>,
+            resume: Option<DumpCheckpoint>,
+            on_checkpoint: Option<CheckpointCallback>,
+        ) -> Result<DumpReport> {
+            let fail_after = self.failures.lock().unwrap().pop_front();
+            let mut checkpoint = match resume {
+                Some(checkpoint) => checkpoint,
+                None => {
+                    writer.write_all(HEADER.as_bytes())?;
+                    DumpCheckpoint {
+                        tables: Vec::new(),
+                        offset: HEADER.len() as u64,
+                    }
+                }
+            };
+            for table in TABLES.iter().filter(|t| !checkpoint.tables.iter().any(|done| done == *t)) {
+                let output = table_output(table);
+                if fail_after == Some(checkpoint.tables.len()) {
+                    writer.write_all(&output.as_bytes()[..output.len() / 2])?;
+                    writer.flush()?;
+                    return Err(BackupError::Database(DatabaseErrorKind::Timeout, "timed out".to_string()));
+                }
+                writer.write_all(output.as_bytes())?;
+                writer.flush()?;
+                checkpoint.tables.push(table.to_string());
+                checkpoint.offset += output.len() as u64;
+                if let Some(callback) = &on_checkpoint {
+                    callback(&checkpoint);
+                }
+            }
+            Ok(DumpReport::default())
+        }
+        async fn change_marker(&self, _db_name: &str) -> Result<Option<String>> {
+            Ok(None)
+        }
+        async fn restore_database(&self, _db_name: &str, _reader: Box<dyn BufRead + Send>) -> Result<()> {
+            Ok(())
+        }
+        async fn drop_database(&self, _db_name: &str) -> Result<()> {
+            Ok(())
+        }
+        async fn row_counts(&self, _db_name: &str) -> Result<BTreeMap<String, u64>> {
+            Ok(BTreeMap::new())
+        }
+        async fn close(&self) -> Result<()> {
+            Ok(())
+        }
+        fn set_pk_ranges(&mut self, _ranges: Vec<PkRange>) {}
+        fn set_dump_format(&mut self, _format: DumpFormat) {}
+        fn engine_name(&self) -> &'static str {
+            "flaky"
+        }
+    }
+
+    async fn dump(driver: &FlakyDriver, sql_path: &Path) -> Result<DumpReport> {
+        dump_with_retry(driver, "app", sql_path, true, Verbosity::Quiet, None, Duration::ZERO).await
+    }
+
+    fn uninterrupted_dump() -> String {
+        std::iter::once(HEADER.to_string()).chain(TABLES.iter().map(|t| table_output(t))).collect()
+    }
+
+    #[tokio::test]
+    async fn test_resumed_dump_matches_uninterrupted_dump() {
+        let dir = tempfile::tempdir().unwrap();
+        let sql_path = dir.path().join("app_20240101_000000.sql");
+
+        dump(&FlakyDriver::new(&[1, 3]), &sql_path).await.unwrap();
+
+        let dumped = fs::read_to_string(&sql_path).unwrap();
+        assert_eq!(dumped, uninterrupted_dump());
+        assert_eq!(dumped.matches(HEADER).count(), 1);
+        assert!(!checkpoint_path(&sql_path).exists());
+    }
+
+    #[tokio::test]
+    async fn test_checkpoint_removed_after_final_failure() {
+        let dir = tempfile::tempdir().unwrap();
+        let sql_path = dir.path().join("app_20240101_000000.sql");
+
+        let failures = vec![2; DUMP_ATTEMPTS as usize];
+        let err = dump(&FlakyDriver::new(&failures), &sql_path).await.unwrap_err();
+        assert!(err.is_transient());
+        assert!(!checkpoint_path(&sql_path).exists());
+    }
+
+    #[tokio::test]
+    async fn test_not_resumable_starts_over() {
+        let dir = tempfile::tempdir().unwrap();
+        let sql_path = dir.path().join("app_20240101_000000.sql");
+
+        let driver = FlakyDriver::new(&[2]);
+        dump_with_retry(&driver, "app", &sql_path, false, Verbosity::Quiet, None, Duration::ZERO)
+            .await
+            .unwrap();
+        assert_eq!(fs::read_to_string(&sql_path).unwrap(), uninterrupted_dump());
+    }
+
+    #[test]
+    fn test_open_checkpoint_truncates_to_offset() {
+        let dir = tempfile::tempdir().unwrap();
+        let sql_path = dir.path().join("app.sql");
+        let checkpoint_path = checkpoint_path(&sql_path);
+        fs::write(&sql_path, "-- header\nINSERT complete;\nINSERT half").unwrap();
+        let checkpoint = DumpCheckpoint {
+            tables: vec!["users".to_string()],
+            offset: "-- header\nINSERT complete;\n".len() as u64,
+        };
+        fs::write(&checkpoint_path, serde_json::to_vec(&checkpoint).unwrap()).unwrap();
+
+        assert_eq!(open_checkpoint(&sql_path, &checkpoint_path), Some(checkpoint));
+        assert_eq!(fs::read_to_string(&sql_path).unwrap(), "-- header\nINSERT complete;\n");
     }
 
-    results
+    #[test]
+    fn test_open_checkpoint_rejects_shorter_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let sql_path = dir.path().join("app.sql");
+        let checkpoint_path = checkpoint_path(&sql_path);
+        fs::write(&sql_path, "-- header\n").unwrap();
+        let checkpoint = DumpCheckpoint {
+            tables: vec!["users".to_string()],
+            offset: 100,
+        };
+        fs::write(&checkpoint_path, serde_json::to_vec(&checkpoint).unwrap()).unwrap();
+
+        assert_eq!(open_checkpoint(&sql_path, &checkpoint_path), None);
+        assert_eq!(fs::read_to_string(&sql_path).unwrap(), "-- header\n");
+
+        fs::write(&checkpoint_path, b"not json").unwrap();
+        assert_eq!(open_checkpoint(&sql_path, &checkpoint_path), None);
+    }
+
+    fn archived(db_errors: &[&str]) -> BackupResult {
+        BackupResult {
+            success: true,
+            file_path: Some(PathBuf::from("prod.zip")),
+            file_size: Some(3 * 1024 * 1024 / 2),
+            duration_secs: 23,
+            error: None,
+            db_errors: db_errors.iter().map(|db| (db.to_string(), "failed".to_string())).collect(),
+            ..BackupResult::failure("prod", vec!["app".to_string(), "crm".to_string()], Instant::now(), String::new(), Vec::new(), &[])
+        }
+    }
+
+    #[test]
+    fn test_summary_line_ok() {
+        assert_eq!(archived(&[]).summary_line(), "[OK] prod: 2 DBs, 1.5MB in 23s");
+    }
+
+    #[test]
+    fn test_summary_line_partial() {
+        assert_eq!(
+            archived(&["crm"]).summary_line(),
+            "[PARTIAL] prod: 2 DBs, 1.5MB in 23s (1 DB failed: crm)"
+        );
+        assert_eq!(
+            archived(&["crm", "logs"]).summary_line(),
+            "[PARTIAL] prod: 2 DBs, 1.5MB in 23s (2 DBs failed: crm, logs)"
+        );
+    }
+
+    #[test]
+    fn test_summary_line_fail() {
+        let result = BackupResult::failure(
+            "prod",
+            vec!["app".to_string()],
+            Instant::now(),
+            "connection refused".to_string(),
+            vec![("app".to_string(), "connection refused".to_string())],
+            &[],
+        );
+        assert_eq!(result.summary_line(), "[FAIL] prod: connection refused (1 DB failed: app) after 0s");
+
+        let result = BackupResult { error: None, ..result };
+        assert!(result.summary_line().starts_with("[FAIL] prod: Unknown error"));
+    }
+
+    #[test]
+    fn test_summary_line_skip() {
+        let result = BackupResult::unchanged("prod", Instant::now(), vec!["app".to_string(), "crm".to_string()], &[]);
+        assert_eq!(result.summary_line(), "[SKIP] prod: 2 DBs unchanged");
+    }
 }
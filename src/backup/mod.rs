@@ -1,6 +1,14 @@
+pub mod change_tracker;
 pub mod compression;
+pub mod inventory;
 pub mod job;
+pub mod queue;
+pub mod restore;
+pub mod retention;
+pub mod schedule_state;
 pub mod scheduler;
+pub mod shutdown;
+pub mod watchdog;
 
 pub use job::{execute_all_jobs, execute_job_backup, BackupResult};
-pub use scheduler::run_scheduler;
+pub use scheduler::{run_scheduler, run_scheduler_once};
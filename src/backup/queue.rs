@@ -0,0 +1,312 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
+
+/// Finished jobs kept for display; older ones are dropped.
+const MAX_FINISHED_JOBS: usize = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobState {
+    Pending,
+    Running,
+    Done,
+    Failed,
+    Cancelled,
+}
+
+impl JobState {
+    pub fn is_active(&self) -> bool {
+        matches!(self, JobState::Pending | JobState::Running)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct QueuedJob {
+    pub id: u64,
+    pub connection_name: String,
+    pub databases: Vec<String>,
+    pub state: JobState,
+    pub queued_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+/// Signals a running backup to stop. Clones share the same flag.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<(AtomicBool, Notify)>);
+
+impl CancelToken {
+    pub fn cancel(&self) {
+        self.0 .0.store(true, Ordering::SeqCst);
+        self.0 .1.notify_waiters();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0 .0.load(Ordering::SeqCst)
+    }
+
+    /// Completes once `cancel` has been called.
+    pub async fn cancelled(&self) {
+        loop {
+            // Created before the check so a cancel in between still wakes us.
+            let notified = self.0 .1.notified();
+            if self.is_cancelled() {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
+
+/// Backup jobs of the current and recent runs, shared by the menu, scheduler and API.
+#[derive(Debug, Default)]
+pub struct JobQueue {
+    next_id: AtomicU64,
+    jobs: Mutex<Vec<(QueuedJob, CancelToken)>>,
+    closed: AtomicBool,
+}
+
+impl JobQueue {
+    pub fn enqueue(&self, connection_name: &str, databases: &[String]) -> (u64, CancelToken) {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst) + 1;
+        let token = CancelToken::default();
+        let job = QueuedJob {
+            id,
+            connection_name: connection_name.to_string(),
+            databases: databases.to_vec(),
+            state: JobState::Pending,
+            queued_at: Utc::now(),
+            started_at: None,
+            finished_at: None,
+        };
+        let mut jobs = self.jobs.lock().unwrap_or_else(|e| e.into_inner());
+        jobs.push((job, token.clone()));
+        Self::trim_finished(&mut jobs);
+        (id, token)
+    }
+
+    /// Marks a pending job as running. Returns false if it was cancelled while queued
+    /// or the queue has been closed.
+    pub fn start(&self, id: u64) -> bool {
+        let mut jobs = self.jobs.lock().unwrap_or_else(|e| e.into_inner());
+        match jobs.iter_mut().find(|(job, _)| job.id == id) {
+            Some((job, token)) if job.state == JobState::Pending && self.is_closed() => {
+                job.state = JobState::Cancelled;
+                job.finished_at = Some(Utc::now());
+                token.cancel();
+                false
+            }
+            Some((job, _)) if job.state == JobState::Pending => {
+                job.state = JobState::Running;
+                job.started_at = Some(Utc::now());
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn finish(&self, id: u64, success: bool) {
+        let mut jobs = self.jobs.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((job, token)) = jobs.iter_mut().find(|(job, _)| job.id == id) {
+            job.state = if token.is_cancelled() {
+                JobState::Cancelled
+            } else if success {
+                JobState::Done
+            } else {
+                JobState::Failed
+            };
+            job.finished_at = Some(Utc::now());
+        }
+    }
+
+    /// Starts job `id`, runs `work` with its cancel token and marks the job done or failed
+    /// according to `succeeded`. Returns `None` without running `work` if the job was
+    /// cancelled before it could start.
+    pub async fn run<T, Fut>(
+        &self,
+        (id, token): (u64, CancelToken),
+        work: impl FnOnce(CancelToken) -> Fut,
+        succeeded: impl FnOnce(&T) -> bool,
+    ) -> Option<T>
+    where
+        Fut: Future<Output = T>,
+    {
+        if !self.start(id) {
+            return None;
+        }
+        let output = work(token).await;
+        self.finish(id, succeeded(&output));
+        Some(output)
+    }
+
+    /// Cancels a pending job before it starts, or aborts a running one.
+    /// Returns false if the job is unknown or already finished.
+    pub fn cancel(&self, id: u64) -> bool {
+        let mut jobs = self.jobs.lock().unwrap_or_else(|e| e.into_inner());
+        let Some((job, token)) = jobs.iter_mut().find(|(job, _)| job.id == id) else {
+            return false;
+        };
+        match job.state {
+            JobState::Pending => {
+                job.state = JobState::Cancelled;
+                job.finished_at = Some(Utc::now());
+            }
+            JobState::Running => {}
+            _ => return false,
+        }
+        token.cancel();
+        true
+    }
+
+    /// Lets running jobs finish but starts no new ones; pending jobs are cancelled.
+    pub fn close(&self) {
+        self.closed.store(true, Ordering::SeqCst);
+        let mut jobs = self.jobs.lock().unwrap_or_else(|e| e.into_inner());
+        for (job, token) in jobs.iter_mut().filter(|(job, _)| job.state == JobState::Pending) {
+            job.state = JobState::Cancelled;
+            job.finished_at = Some(Utc::now());
+            token.cancel();
+        }
+    }
+
+    fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::SeqCst)
+    }
+
+    pub fn has_running(&self) -> bool {
+        let jobs = self.jobs.lock().unwrap_or_else(|e| e.into_inner());
+        jobs.iter().any(|(job, _)| job.state == JobState::Running)
+    }
+
+    /// Aborts every running job. Returns how many were cancelled.
+    pub fn cancel_running(&self) -> usize {
+        let jobs = self.jobs.lock().unwrap_or_else(|e| e.into_inner());
+        let running: Vec<&CancelToken> = jobs
+            .iter()
+            .filter(|(job, _)| job.state == JobState::Running)
+            .map(|(_, token)| token)
+            .collect();
+        running.iter().for_each(|token| token.cancel());
+        running.len()
+    }
+
+    pub fn snapshot(&self) -> Vec<QueuedJob> {
+        let jobs = self.jobs.lock().unwrap_or_else(|e| e.into_inner());
+        jobs.iter().map(|(job, _)| job.clone()).collect()
+    }
+
+    fn trim_finished(jobs: &mut Vec<(QueuedJob, CancelToken)>) {
+        let finished = jobs.iter().filter(|(job, _)| !job.state.is_active()).count();
+        let mut excess = finished.saturating_sub(MAX_FINISHED_JOBS);
+        jobs.retain(|(job, _)| {
+            if excess > 0 && !job.state.is_active() {
+                excess -= 1;
+                return false;
+            }
+            true
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(queue: &JobQueue, id: u64) -> Option<JobState> {
+        queue.snapshot().into_iter().find(|job| job.id == id).map(|job| job.state)
+    }
+
+    #[test]
+    fn test_start_after_cancel_fails() {
+        let queue = JobQueue::default();
+        let (id, token) = queue.enqueue("prod", &[]);
+        assert!(queue.cancel(id));
+        assert!(token.is_cancelled());
+        assert!(!queue.start(id));
+        assert_eq!(state(&queue, id), Some(JobState::Cancelled));
+        // Already finished, so there is nothing left to cancel.
+        assert!(!queue.cancel(id));
+    }
+
+    #[test]
+    fn test_cancel_running_job() {
+        let queue = JobQueue::default();
+        let (id, token) = queue.enqueue("prod", &[]);
+        assert!(queue.start(id));
+        assert!(queue.cancel(id));
+        assert!(token.is_cancelled());
+        // Stays running until the backup notices the token and returns.
+        assert_eq!(state(&queue, id), Some(JobState::Running));
+
+        queue.finish(id, true);
+        assert_eq!(state(&queue, id), Some(JobState::Cancelled));
+    }
+
+    #[test]
+    fn test_finish_records_outcome() {
+        let queue = JobQueue::default();
+        let (ok, _) = queue.enqueue("a", &[]);
+        let (failed, _) = queue.enqueue("b", &[]);
+        assert!(queue.start(ok) && queue.start(failed));
+        assert!(!queue.start(ok));
+        queue.finish(ok, true);
+        queue.finish(failed, false);
+        assert_eq!(state(&queue, ok), Some(JobState::Done));
+        assert_eq!(state(&queue, failed), Some(JobState::Failed));
+        assert!(!queue.has_running());
+    }
+
+    #[test]
+    fn test_close_and_cancel_running() {
+        let queue = JobQueue::default();
+        let (running, running_token) = queue.enqueue("a", &[]);
+        let (pending, pending_token) = queue.enqueue("b", &[]);
+        assert!(queue.start(running));
+
+        queue.close();
+        assert_eq!(state(&queue, pending), Some(JobState::Cancelled));
+        assert!(pending_token.is_cancelled());
+        assert!(!running_token.is_cancelled());
+        let (late, _) = queue.enqueue("c", &[]);
+        assert!(!queue.start(late));
+        assert_eq!(state(&queue, late), Some(JobState::Cancelled));
+
+        assert!(queue.has_running());
+        assert_eq!(queue.cancel_running(), 1);
+        assert!(running_token.is_cancelled());
+    }
+
+    #[test]
+    fn test_trim_finished() {
+        let queue = JobQueue::default();
+        let (active, _) = queue.enqueue("active", &[]);
+        for _ in 0..MAX_FINISHED_JOBS + 5 {
+            let (id, _) = queue.enqueue("done", &[]);
+            queue.start(id);
+            queue.finish(id, true);
+        }
+        queue.enqueue("last", &[]);
+
+        let jobs = queue.snapshot();
+        assert_eq!(jobs.iter().filter(|job| !job.state.is_active()).count(), MAX_FINISHED_JOBS);
+        assert!(jobs.iter().any(|job| job.id == active));
+    }
+
+    #[tokio::test]
+    async fn test_run_skips_cancelled_jobs() {
+        let queue = JobQueue::default();
+        let job = queue.enqueue("prod", &[]);
+        let output = queue.run(job, |_| async { 7 }, |n| *n == 7).await;
+        assert_eq!(output, Some(7));
+        assert_eq!(state(&queue, 1), Some(JobState::Done));
+
+        let (id, token) = queue.enqueue("prod", &[]);
+        queue.cancel(id);
+        let output = queue.run((id, token), |_| async { 7 }, |_| true).await;
+        assert_eq!(output, None);
+    }
+}
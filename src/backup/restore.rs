@@ -0,0 +1,304 @@
+use crate::backup::compression::read_manifest;
+use crate::backup::job::COMBINED_CONNECTION_NAME;
+use crate::config::{AppConfig, DatabaseConfig};
+use crate::database::{create_driver, DatabaseDriver};
+use crate::error::{BackupError, Result};
+use crate::upload::BackupMetadata;
+use std::fs::{self, File};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeSet;
+use std::io::{BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+use zip::ZipArchive;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArchiveEntry {
+    pub entry_name: String,
+    pub connection: Option<String>,
+    pub database: String,
+}
+
+impl std::fmt::Display for ArchiveEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.connection {
+            Some(conn) => write!(f, "{}/{}", conn, self.database),
+            None => write!(f, "{}", self.database),
+        }
+    }
+}
+
+pub fn list_archives(config: &AppConfig) -> Vec<PathBuf> {
+    let mut archives = Vec::new();
+    let mut dirs = vec![config.local_backup_dir.clone()];
+    if let Ok(entries) = fs::read_dir(&config.local_backup_dir) {
+        dirs.extend(entries.flatten().map(|e| e.path()).filter(|p| p.is_dir()));
+    }
+
+    for dir in dirs {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        archives.extend(
+            entries
+                .flatten()
+                .map(|e| e.path())
+                .filter(|p| p.is_file() && p.extension().is_some_and(|ext| ext == "zip")),
+        );
+    }
+
+    archives.sort_by_key(|p| std::cmp::Reverse(fs::metadata(p).and_then(|m| m.modified()).ok()));
+    archives
+}
+
+fn parse_entry_name(entry_name: &str) -> Option<ArchiveEntry> {
+    let stem = entry_name.strip_suffix(".sql")?;
+    let (connection, file_stem) = match stem.rsplit_once('/') {
+        Some((conn, file)) => (Some(conn.to_string()), file),
+        None => (None, stem),
+    };
+
+    // Entries are named `{db}_{YYYYmmdd}_{HHMMSS}.sql`; strip the timestamp.
+    let parts: Vec<&str> = file_stem.rsplitn(3, '_').collect();
+    let database = match parts[..] {
+        [time, date, db] if time.len() == 6 && date.len() == 8 => db,
+        _ => file_stem,
+    };
+
+    Some(ArchiveEntry {
+        entry_name: entry_name.to_string(),
+        connection,
+        database: database.to_string(),
+    })
+}
+
+pub fn list_entries(archive_path: &Path) -> Result<Vec<ArchiveEntry>> {
+    let archive = ZipArchive::new(File::open(archive_path)?)?;
+    Ok(archive.file_names().filter_map(parse_entry_name).collect())
+}
+
+pub fn default_connection(archive_path: &Path, entry: &ArchiveEntry) -> Option<String> {
+    entry
+        .connection
+        .clone()
+        .or_else(|| {
+            BackupMetadata::read_from_archive(archive_path)
+                .map(|m| m.connection_name)
+                .filter(|name| name != COMBINED_CONNECTION_NAME)
+        })
+        .or_else(|| {
+            archive_path
+                .parent()
+                .and_then(|p| p.file_name())
+                .map(|name| name.to_string_lossy().to_string())
+        })
+}
+
+fn extract_verified(archive_path: &Path, entry: &ArchiveEntry, dest: &Path) -> Result<()> {
+    let mut archive = ZipArchive::new(File::open(archive_path)?)?;
+    let expected = read_manifest(&mut archive)?.and_then(|m| m.get(&entry.entry_name).cloned());
+
+    let mut zip_entry = archive.by_name(&entry.entry_name)?;
+    let mut out = File::create(dest)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+    loop {
+        let bytes_read = zip_entry.read(&mut buffer)?;
+        if bytes_read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..bytes_read]);
+        out.write_all(&buffer[..bytes_read])?;
+    }
+
+    let actual = format!("{:x}", hasher.finalize());
+    match expected {
+        Some(hash) if hash != actual => Err(BackupError::Compression(format!(
+            "Checksum mismatch for {}: archive entry is corrupted",
+            entry.entry_name
+        ))),
+        Some(_) => Ok(()),
+        None => {
+            warn!("No manifest checksum for {}, skipping verification", entry.entry_name);
+            Ok(())
+        }
+    }
+}
+
+pub async fn restore_entry(
+    db_config: &DatabaseConfig,
+    archive_path: &Path,
+    entry: &ArchiveEntry,
+    target_database: &str,
+) -> Result<()> {
+    info!(
+        "Restoring {} from {} into {}/{}",
+        entry.entry_name,
+        archive_path.display(),
+        db_config.name,
+        target_database
+    );
+
+    // Extract first: zip entries borrow the archive and can't be held across awaits.
+    let sql_path = std::env::temp_dir().join(format!(
+        "tlm_restore_{}_{}.sql",
+        std::process::id(),
+        target_database
+    ));
+    if let Err(e) = extract_verified(archive_path, entry, &sql_path) {
+        let _ = fs::remove_file(&sql_path);
+        return Err(e);
+    }
+
+    let driver = create_driver(db_config)?;
+    let reader = BufReader::new(File::open(&sql_path)?);
+    let result = driver
+        .restore_database(target_database, Box::new(reader))
+        .await;
+    let _ = driver.close().await;
+    let _ = fs::remove_file(&sql_path);
+
+    result.map_err(|e| match e {
+        BackupError::Database(kind, msg) => {
+            BackupError::Database(kind, format!("Failed to restore {}: {}", target_database, msg))
+        }
+        e => BackupError::database(format!("Failed to restore {}: {}", target_database, e)),
+    })
+}
+
+const SCRATCH_PREFIX: &str = "tlm_restore_test_";
+
+/// MySQL identifiers are limited to 64 characters.
+const MAX_DATABASE_NAME_LEN: usize = 64;
+
+#[derive(Debug, Clone)]
+pub struct TestRestoreReport {
+    pub entry: ArchiveEntry,
+    pub error: Option<String>,
+    /// Tables whose restored row count differs from the source: (table, restored, source).
+    pub row_count_mismatches: Vec<(String, u64, u64)>,
+}
+
+impl TestRestoreReport {
+    pub fn is_ok(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+fn scratch_database_name(database: &str) -> String {
+    format!("{}{}", SCRATCH_PREFIX, database)
+        .chars()
+        .take(MAX_DATABASE_NAME_LEN)
+        .collect()
+}
+
+/// Restores every dump in `archive_path` into a throwaway database on the
+/// `scratch_connection`, optionally compares row counts with the source
+/// connection, and drops the scratch database again.
+pub async fn test_restore(
+    config: &AppConfig,
+    archive_path: &Path,
+    compare_row_counts: bool,
+) -> Result<Vec<TestRestoreReport>> {
+    let scratch_name = config
+        .scratch_connection
+        .as_deref()
+        .ok_or_else(|| BackupError::Config("No scratch_connection configured for test restores".to_string()))?;
+    let scratch = config
+        .databases
+        .iter()
+        .find(|d| d.name == scratch_name)
+        .ok_or_else(|| BackupError::Config(format!("Unknown scratch connection '{}'", scratch_name)))?;
+    let scratch_driver = create_driver(scratch)?;
+
+    let entries = list_entries(archive_path)?;
+    if entries.is_empty() {
+        return Err(BackupError::Config(format!(
+            "No SQL dumps in {} (NDJSON dumps can't be restored)",
+            archive_path.display()
+        )));
+    }
+
+    let mut reports = Vec::new();
+    for entry in entries {
+        let scratch_db = scratch_database_name(&entry.database);
+        let mut report = TestRestoreReport {
+            entry: entry.clone(),
+            error: None,
+            row_count_mismatches: Vec::new(),
+        };
+
+        match restore_entry(scratch, archive_path, &entry, &scratch_db).await {
+            Ok(()) if compare_row_counts => {
+                match compare_counts(config, archive_path, &entry, scratch_driver.as_ref(), &scratch_db).await {
+                    Ok(mismatches) => report.row_count_mismatches = mismatches,
+                    Err(e) => warn!("Could not compare row counts for {}: {}", entry, e),
+                }
+            }
+            Ok(()) => {}
+            Err(e) => report.error = Some(e.to_string()),
+        }
+
+        if let Err(e) = scratch_driver.drop_database(&scratch_db).await {
+            warn!("Failed to drop scratch database {}: {}", scratch_db, e);
+        }
+        reports.push(report);
+    }
+    let _ = scratch_driver.close().await;
+    Ok(reports)
+}
+
+async fn compare_counts(
+    config: &AppConfig,
+    archive_path: &Path,
+    entry: &ArchiveEntry,
+    scratch_driver: &dyn DatabaseDriver,
+    scratch_db: &str,
+) -> Result<Vec<(String, u64, u64)>> {
+    let source_name = default_connection(archive_path, entry)
+        .ok_or_else(|| BackupError::Config(format!("Cannot tell which connection {} came from", entry)))?;
+    let source = config
+        .databases
+        .iter()
+        .find(|d| d.name == source_name)
+        .ok_or_else(|| BackupError::Config(format!("Unknown source connection '{}'", source_name)))?;
+
+    let restored = scratch_driver.row_counts(scratch_db).await?;
+    let source_driver = create_driver(source)?;
+    let counted = source_driver.row_counts(&entry.database).await;
+    let _ = source_driver.close().await;
+    let original = counted?;
+    let tables: BTreeSet<&String> = restored.keys().chain(original.keys()).collect();
+    Ok(tables
+        .into_iter()
+        .filter_map(|table| {
+            let restored_count = restored.get(table).copied().unwrap_or(0);
+            let source_count = original.get(table).copied().unwrap_or(0);
+            (restored_count != source_count).then(|| (table.clone(), restored_count, source_count))
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_entry_name() {
+        let entry = parse_entry_name("prod/shop_data_20240101_120000.sql").unwrap();
+        assert_eq!(entry.connection.as_deref(), Some("prod"));
+        assert_eq!(entry.database, "shop_data");
+
+        let entry = parse_entry_name("shop_20240101_120000.sql").unwrap();
+        assert_eq!(entry.connection, None);
+        assert_eq!(entry.database, "shop");
+
+        assert!(parse_entry_name("MANIFEST.sha256").is_none());
+    }
+
+    #[test]
+    fn test_scratch_database_name_fits_identifier_limit() {
+        assert_eq!(scratch_database_name("shop"), "tlm_restore_test_shop");
+        assert_eq!(scratch_database_name(&"x".repeat(80)).len(), MAX_DATABASE_NAME_LEN);
+    }
+}
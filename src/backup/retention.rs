@@ -0,0 +1,314 @@
+use crate::backup::inventory::PENDING_EXTENSION;
+use crate::backup::job::CHECKPOINT_EXTENSION;
+use crate::config::DumpFormat;
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tracing::{info, warn};
+
+/// Extension of the empty marker that keeps an archive out of quota pruning.
+const PINNED_EXTENSION: &str = "pinned";
+
+fn pin_marker_path(archive_path: &Path) -> PathBuf {
+    let mut name = archive_path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{}", PINNED_EXTENSION));
+    archive_path.with_file_name(name)
+}
+
+pub fn is_pinned(archive_path: &Path) -> bool {
+    pin_marker_path(archive_path).exists()
+}
+
+pub fn set_pinned(archive_path: &Path, pinned: bool) -> std::io::Result<()> {
+    let marker = pin_marker_path(archive_path);
+    if pinned {
+        return fs::write(marker, b"");
+    }
+    match fs::remove_file(marker) {
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        result => result,
+    }
+}
+
+/// An archive with the files that go with it: split volumes and their `.parts` manifest,
+/// the metadata sidecar, and dumps kept next to it. An `.incomplete` archive stands in
+/// for the archive, and dumps whose archive is elsewhere form a set of their own.
+#[derive(Debug)]
+struct BackupSet {
+    files: Vec<PathBuf>,
+    size: u64,
+    modified: SystemTime,
+    pinned: bool,
+}
+
+impl BackupSet {
+    fn add(&mut self, path: PathBuf) {
+        if let Ok(meta) = fs::metadata(&path) {
+            self.size += meta.len();
+            self.modified = self.modified.max(meta.modified().unwrap_or(SystemTime::UNIX_EPOCH));
+        }
+        self.files.push(path);
+    }
+}
+
+/// Splits `{stem}.zip.{suffix}` into the archive name and the suffix (empty for the
+/// archive itself).
+fn archive_part(name: &str) -> Option<(&str, &str)> {
+    let end = name.rfind(".zip")? + ".zip".len();
+    match &name[end..] {
+        "" => Some((name, "")),
+        rest => rest.strip_prefix('.').map(|suffix| (&name[..end], suffix)),
+    }
+}
+
+/// The `%Y%m%d_%H%M%S` run timestamp a dump name ends with, e.g. `shop_20240101_120000`.
+fn dump_timestamp(stem: &str) -> Option<&str> {
+    let timestamp = stem.get(stem.len().checked_sub(15)?..)?;
+    let is_timestamp = timestamp.char_indices().all(|(i, c)| if i == 8 { c == '_' } else { c.is_ascii_digit() });
+    (is_timestamp && stem[..stem.len() - 15].ends_with('_')).then_some(timestamp)
+}
+
+/// Backup sets directly in `dir`, oldest first. Sets of a run that is still writing them
+/// (a `.pending` marker or a dump checkpoint) are left out.
+fn list_backup_sets(dir: &Path) -> Vec<BackupSet> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let dump_extensions = [DumpFormat::Sql.extension(), DumpFormat::Ndjson.extension()];
+    let mut archives: BTreeMap<String, Vec<PathBuf>> = BTreeMap::new();
+    let mut dumps: Vec<(String, PathBuf)> = Vec::new();
+    let mut excluded: HashSet<String> = HashSet::new();
+    let mut pinned: HashSet<String> = HashSet::new();
+    let mut busy_timestamps: HashSet<String> = HashSet::new();
+
+    for path in entries.flatten().map(|e| e.path()).filter(|p| p.is_file()) {
+        let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+        if let Some((archive, suffix)) = archive_part(&name) {
+            match suffix {
+                "" | "parts" | "json" | "incomplete" => {}
+                s if !s.is_empty() && s.chars().all(|c| c.is_ascii_digit()) => {}
+                s if s == PINNED_EXTENSION => {
+                    pinned.insert(archive.to_string());
+                    continue;
+                }
+                s if s == PENDING_EXTENSION => {
+                    excluded.insert(archive.to_string());
+                    continue;
+                }
+                _ => continue,
+            }
+            archives.entry(archive.to_string()).or_default().push(path);
+        } else {
+            let checkpoint = format!(".{}", CHECKPOINT_EXTENSION);
+            let (dump, busy) = match name.strip_suffix(checkpoint.as_str()) {
+                Some(dump) => (dump, true),
+                None => (name.as_str(), false),
+            };
+            let Some(timestamp) = dump_extensions
+                .iter()
+                .find_map(|ext| dump.strip_suffix(format!(".{}", ext).as_str()))
+                .and_then(dump_timestamp)
+            else {
+                continue;
+            };
+            if busy {
+                busy_timestamps.insert(timestamp.to_string());
+            } else {
+                dumps.push((timestamp.to_string(), path));
+            }
+        }
+    }
+
+    let new_set = || BackupSet {
+        files: Vec::new(),
+        size: 0,
+        modified: SystemTime::UNIX_EPOCH,
+        pinned: false,
+    };
+    let mut sets: BTreeMap<String, BackupSet> = BTreeMap::new();
+    for (archive, files) in archives {
+        let is_pinned = pinned.contains(&archive);
+        let set = sets.entry(archive).or_insert_with(new_set);
+        files.into_iter().for_each(|path| set.add(path));
+        set.pinned = is_pinned;
+    }
+    for (timestamp, path) in dumps {
+        let run = format!("_{}", timestamp);
+        let key = sets
+            .keys()
+            .find(|archive| archive.contains(&run))
+            .cloned()
+            .unwrap_or(timestamp);
+        sets.entry(key).or_insert_with(new_set).add(path);
+    }
+
+    let mut sets: Vec<(String, BackupSet)> = sets
+        .into_iter()
+        .filter(|(key, _)| !excluded.contains(key) && !busy_timestamps.iter().any(|t| key.contains(t.as_str())))
+        .collect();
+    sets.sort_by(|a, b| a.1.modified.cmp(&b.1.modified).then_with(|| a.0.cmp(&b.0)));
+    sets.into_iter().map(|(_, set)| set).collect()
+}
+
+/// Indices of the oldest archives to delete so the total fits in `max_total_bytes`,
+/// or an error if that isn't enough. `archives` holds each archive's size and whether
+/// it is pinned, oldest first; pinned archives and the newest `keep_last` are kept.
+fn plan_pruning(archives: &[(u64, bool)], max_total_bytes: u64, keep_last: usize) -> std::result::Result<Vec<usize>, String> {
+    let mut total: u64 = archives.iter().map(|(size, _)| size).sum();
+    let prunable = archives.len().saturating_sub(keep_last);
+    let mut pruned = Vec::new();
+    for (i, (size, pinned)) in archives[..prunable].iter().enumerate() {
+        if total <= max_total_bytes {
+            break;
+        }
+        if !pinned {
+            total -= size;
+            pruned.push(i);
+        }
+    }
+
+    if total > max_total_bytes {
+        return Err(format!(
+            "Backup quota exceeded: the {} remaining archive(s) use {} bytes, over max_total_bytes ({}). \
+             Raise max_total_bytes, lower keep_last or unpin archives",
+            archives.len() - pruned.len(),
+            total,
+            max_total_bytes
+        ));
+    }
+    Ok(pruned)
+}
+
+/// Deletes the oldest backups in `dir` until their combined size is within
+/// `max_total_bytes`, never touching the newest `keep_last`. Each backup is an archive
+/// together with its volumes, sidecar and kept dumps (see [`BackupSet`]). Pinned
+/// archives count towards the total but are never pruned; when they alone exceed the
+/// quota nothing is deleted and a warning is logged. Returns the deleted paths.
+pub fn enforce_quota(dir: &Path, max_total_bytes: u64, keep_last: usize) -> std::result::Result<Vec<PathBuf>, String> {
+    let sets = list_backup_sets(dir);
+    let pinned_total: u64 = sets.iter().filter(|set| set.pinned).map(|set| set.size).sum();
+    if pinned_total > max_total_bytes {
+        warn!(
+            "Pinned archives in {} alone use {} bytes, over max_total_bytes ({}); nothing is pruned. \
+             Unpin archives or raise max_total_bytes",
+            dir.display(),
+            pinned_total,
+            max_total_bytes
+        );
+        return Ok(Vec::new());
+    }
+    let archives: Vec<(u64, bool)> = sets.iter().map(|set| (set.size, set.pinned)).collect();
+    let indices = plan_pruning(&archives, max_total_bytes, keep_last)?;
+
+    let mut pruned = Vec::new();
+    for set in indices.iter().map(|&i| &sets[i]) {
+        for path in &set.files {
+            fs::remove_file(path).map_err(|e| format!("Failed to prune {}: {}", path.display(), e))?;
+            pruned.push(path.clone());
+        }
+        if let Some(first) = set.files.first() {
+            info!(
+                "Pruned {} ({} file(s), {} bytes) to stay under max_total_bytes",
+                first.display(),
+                set.files.len(),
+                set.size
+            );
+        }
+    }
+    Ok(pruned)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan_pruning() {
+        let archives = [(400, false), (300, false), (200, false), (100, false)];
+
+        assert_eq!(plan_pruning(&archives, 1000, 1), Ok(vec![]));
+        assert_eq!(plan_pruning(&archives, 600, 1), Ok(vec![0]));
+        assert_eq!(plan_pruning(&archives, 300, 1), Ok(vec![0, 1]));
+        // Pruning down to the newest two still leaves 300 bytes.
+        assert!(plan_pruning(&archives, 250, 2).is_err());
+    }
+
+    #[test]
+    fn test_plan_pruning_skips_pinned() {
+        let archives = [(400, true), (300, false), (200, false), (100, false)];
+
+        // The pinned archive counts, so the next oldest go instead.
+        assert_eq!(plan_pruning(&archives, 600, 1), Ok(vec![1, 2]));
+        assert!(plan_pruning(&archives, 450, 1).is_err());
+    }
+
+    #[test]
+    fn test_pinned_archives_are_kept() {
+        let dir = tempfile::tempdir().unwrap();
+        let pinned = dir.path().join("backup_a.zip");
+        let unpinned = dir.path().join("backup_b.zip");
+        fs::write(&pinned, b"pinned").unwrap();
+        fs::write(&unpinned, b"unpinned").unwrap();
+        set_pinned(&pinned, true).unwrap();
+
+        // Both count towards the quota, but only the unpinned one is pruned.
+        assert_eq!(enforce_quota(dir.path(), 10, 0), Ok(vec![unpinned.clone()]));
+        assert!(pinned.exists() && is_pinned(&pinned));
+
+        // Pins alone over the quota: nothing can be pruned, so nothing is.
+        fs::write(&unpinned, b"unpinned").unwrap();
+        assert_eq!(enforce_quota(dir.path(), 4, 0), Ok(vec![]));
+        assert!(pinned.exists() && unpinned.exists());
+
+        set_pinned(&pinned, false).unwrap();
+        assert!(!is_pinned(&pinned));
+        set_pinned(&pinned, false).unwrap();
+    }
+
+    #[test]
+    fn test_dump_timestamp() {
+        assert_eq!(dump_timestamp("shop_20240101_120000"), Some("20240101_120000"));
+        assert_eq!(dump_timestamp("my_shop_20240101_120000"), Some("20240101_120000"));
+        assert_eq!(dump_timestamp("20240101_120000"), None);
+        assert_eq!(dump_timestamp("shop_2024010_1120000"), None);
+        assert_eq!(dump_timestamp("notes"), None);
+    }
+
+    #[test]
+    fn test_quota_prunes_whole_backup_sets() {
+        let dir = tempfile::tempdir().unwrap();
+        let write = |name: &str, size: usize| {
+            let path = dir.path().join(name);
+            fs::write(&path, vec![0u8; size]).unwrap();
+            path
+        };
+        // Oldest run: split archive with a sidecar and a kept dump.
+        let old = [
+            write("backup_prod_20240101_000000.zip", 100),
+            write("backup_prod_20240101_000000.zip.001", 60),
+            write("backup_prod_20240101_000000.zip.002", 40),
+            write("backup_prod_20240101_000000.zip.parts", 10),
+            write("backup_prod_20240101_000000.zip.json", 10),
+            write("shop_20240101_000000.sql", 80),
+        ];
+        let incomplete = write("backup_prod_20240102_000000.zip.incomplete", 50);
+        let newest = write("backup_prod_20240103_000000.zip", 30);
+        // A run still writing its dump is neither counted nor pruned.
+        let running = [
+            write("shop_20240104_000000.ndjson", 500),
+            write("shop_20240104_000000.ndjson.checkpoint", 10),
+        ];
+        let unrelated = write("notes.txt", 500);
+
+        let sets = list_backup_sets(dir.path());
+        let sizes: Vec<u64> = sets.iter().map(|s| s.size).collect();
+        assert_eq!(sizes, vec![300, 50, 30]);
+
+        let pruned = enforce_quota(dir.path(), 60, 1).unwrap();
+        assert_eq!(pruned.len(), old.len() + 1);
+        assert!(old.iter().chain([&incomplete]).all(|p| !p.exists()));
+        assert!(newest.exists() && unrelated.exists());
+        assert!(running.iter().all(|p| p.exists()));
+    }
+}
@@ -0,0 +1,163 @@
+use crate::config;
+use crate::error::Result;
+use chrono::{DateTime, Utc};
+use fs2::FileExt;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+/// Cron rarely starts exactly on time, so a job that ran one interval ago minus this
+/// much still counts as due.
+const DUE_SLACK_SECS: i64 = 30;
+
+/// When each job last ran, kept on disk so `serve --once` from cron and restarts of
+/// the scheduler only run jobs that are actually due.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ScheduleState {
+    #[serde(default)]
+    last_run: HashMap<String, DateTime<Utc>>,
+}
+
+impl ScheduleState {
+    pub fn path() -> PathBuf {
+        config::config_dir().join("schedule_state.json")
+    }
+
+    /// Held by a `serve --once` cycle, so overlapping cron runs don't run the same jobs.
+    pub fn cycle_lock_path() -> PathBuf {
+        config::config_dir().join("scheduler.lock")
+    }
+
+    /// Takes the exclusive lock on `path` without waiting; it is released when the file
+    /// is dropped. `None` when another process holds it.
+    pub fn try_lock_cycle(path: &Path) -> Result<Option<File>> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let lock = OpenOptions::new().write(true).create(true).truncate(false).open(path)?;
+        if lock.try_lock_exclusive().is_err() {
+            return Ok(None);
+        }
+        Ok(Some(lock))
+    }
+
+    pub fn load() -> Self {
+        Self::load_from(&Self::path())
+    }
+
+    pub fn load_from(path: &Path) -> Self {
+        if !path.exists() {
+            return Self::default();
+        }
+        match fs::read_to_string(path).map(|c| serde_json::from_str(&c)) {
+            Ok(Ok(state)) => state,
+            Ok(Err(e)) => {
+                warn!("Ignoring unreadable schedule state {:?}: {}", path, e);
+                Self::default()
+            }
+            Err(e) => {
+                warn!("Failed to read schedule state {:?}: {}", path, e);
+                Self::default()
+            }
+        }
+    }
+
+    /// Merges the runs saved by other processes since loading, keeping the later time of
+    /// each job, and writes the result back under a lock on `{path}.lock`.
+    fn save_to(&mut self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut lock_name = path.file_name().unwrap_or_default().to_os_string();
+        lock_name.push(".lock");
+        let lock = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path.with_file_name(lock_name))?;
+        lock.lock_exclusive()?;
+
+        for (job_key, at) in Self::load_from(path).last_run {
+            let last = self.last_run.entry(job_key).or_insert(at);
+            *last = (*last).max(at);
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn runs(&self) -> impl Iterator<Item = (&String, &DateTime<Utc>)> {
+        self.last_run.iter()
+    }
+
+    pub fn is_due(&self, job_key: &str, interval_secs: u64, now: DateTime<Utc>) -> bool {
+        match self.last_run.get(job_key) {
+            Some(last) => (now - *last).num_seconds() + DUE_SLACK_SECS >= interval_secs as i64,
+            None => true,
+        }
+    }
+
+    pub fn record(&mut self, job_key: &str, at: DateTime<Utc>) {
+        self.record_to(&Self::path(), job_key, at);
+    }
+
+    fn record_to(&mut self, path: &Path, job_key: &str, at: DateTime<Utc>) {
+        self.last_run.insert(job_key.to_string(), at);
+        if let Err(e) = self.save_to(path) {
+            warn!("Failed to save schedule state: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn test_is_due() {
+        let now = Utc::now();
+        let mut state = ScheduleState::default();
+        state.last_run.insert("prod".to_string(), now - Duration::seconds(3600));
+
+        // Never ran.
+        assert!(state.is_due("staging", 3600, now));
+        // Interval elapsed.
+        assert!(state.is_due("prod", 3600, now));
+        // Cron started a little early.
+        assert!(state.is_due("prod", 3600 + DUE_SLACK_SECS as u64, now));
+        // Not due yet.
+        assert!(!state.is_due("prod", 3600 + DUE_SLACK_SECS as u64 + 1, now));
+    }
+
+    #[test]
+    fn test_record_merges_runs_saved_meanwhile() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("schedule_state.json");
+        let now = Utc::now();
+
+        let mut first = ScheduleState::load_from(&path);
+        let mut second = ScheduleState::load_from(&path);
+        first.record_to(&path, "prod", now);
+        second.record_to(&path, "staging", now);
+        second.record_to(&path, "prod", now - Duration::seconds(60));
+
+        let saved = ScheduleState::load_from(&path);
+        assert_eq!(saved.last_run.get("prod"), Some(&now));
+        assert_eq!(saved.last_run.get("staging"), Some(&now));
+    }
+
+    #[test]
+    fn test_cycle_lock_is_exclusive() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("scheduler.lock");
+
+        let held = ScheduleState::try_lock_cycle(&path).unwrap();
+        assert!(held.is_some());
+        assert!(ScheduleState::try_lock_cycle(&path).unwrap().is_none());
+
+        drop(held);
+        assert!(ScheduleState::try_lock_cycle(&path).unwrap().is_some());
+    }
+}
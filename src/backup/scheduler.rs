@@ -1,26 +1,38 @@
+use crate::backup::schedule_state::ScheduleState;
+use crate::backup::{watchdog, BackupResult};
 use crate::config::AppConfig;
+use crate::database::ProgressCallback;
+use crate::log::events::{self, Event};
+use crate::log::Verbosity;
 use crate::web::{AppState, BackupEntry, SchedulerStatus};
 use chrono::{Duration, Utc};
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use tokio::select;
 use tokio::time::sleep;
-pub async fn run_scheduler(config: Arc<AppConfig>, shutdown: Arc<AtomicUsize>, app_state: Arc<AppState>) {
-    app_state.add_log("INFO", "Starting backup scheduler").await;
-
-    if config.backup_jobs.is_empty() {
-        app_state.add_log("WARN", "No backup jobs configured. Scheduler will wait for configuration.").await;
-    }
-    let min_interval = config
+/// Seconds between scheduler cycles: the shortest enabled job interval, or the
+/// idle poll interval when there is nothing to run.
+fn cycle_interval(config: &AppConfig) -> u64 {
+    config
         .backup_jobs
         .iter()
+        .filter(|j| j.enabled)
         .map(|j| j.schedule.as_seconds())
         .min()
-        .unwrap_or(3600);
+        .unwrap_or(config.idle_interval_secs)
+        .max(1)
+}
+
+pub async fn run_scheduler(config: Arc<AppConfig>, shutdown: Arc<AtomicUsize>, app_state: Arc<AppState>) {
+    let mut config = config;
+    app_state.add_log("INFO", "Starting backup scheduler").await;
+    events::emit(Event::SchedulerStarted);
+    let _heartbeat = watchdog::spawn_heartbeat();
+    check_startup(&config, &app_state).await;
+    let mut min_interval = cycle_interval(&config);
 
     app_state.add_log("INFO", &format!("Scheduler interval: {} seconds", min_interval)).await;
-    let mut last_run: std::collections::HashMap<String, std::time::Instant> = 
-        std::collections::HashMap::new();
+    let mut state = load_schedule_state(&app_state);
     let mut first_run = true;
     
     loop {
@@ -31,6 +43,8 @@ pub async fn run_scheduler(config: Arc<AppConfig>, shutdown: Arc<AtomicUsize>, a
                 interval_secs: min_interval,
                 connection_name: None,
                 database_count: 0,
+                progress: None,
+                current_database: None,
             }).await;
             app_state.add_log("INFO", "Scheduler shutdown requested").await;
             break;
@@ -43,6 +57,8 @@ pub async fn run_scheduler(config: Arc<AppConfig>, shutdown: Arc<AtomicUsize>, a
                 interval_secs: min_interval,
                 connection_name: config.backup_jobs.first().map(|j| j.db_config_name.clone()),
                 database_count: config.backup_jobs.iter().map(|j| j.databases.len()).sum(),
+                progress: None,
+                current_database: None,
             }).await;
             select! {
                 _ = sleep(std::time::Duration::from_secs(min_interval)) => {}
@@ -54,6 +70,9 @@ pub async fn run_scheduler(config: Arc<AppConfig>, shutdown: Arc<AtomicUsize>, a
                     app_state.add_log("INFO", "Scheduler shutdown requested during wait").await;
                     break;
                 }
+                _ = app_state.config_changed() => {
+                    app_state.add_log("INFO", "Configuration changed, reloading").await;
+                }
             }
             if shutdown.load(Ordering::Relaxed) > 0 {
                 app_state.update_scheduler(SchedulerStatus {
@@ -62,10 +81,34 @@ pub async fn run_scheduler(config: Arc<AppConfig>, shutdown: Arc<AtomicUsize>, a
                     interval_secs: min_interval,
                     connection_name: None,
                     database_count: 0,
+                    progress: None,
+                    current_database: None,
                 }).await;
                 app_state.add_log("INFO", "Scheduler shutdown requested").await;
                 break;
             }
+
+            // Pick up jobs and settings saved to disk since the last cycle.
+            match crate::config::load() {
+                Ok(fresh) => {
+                    let jobs_before = config.backup_jobs.iter().filter(|j| j.enabled).count();
+                    config = Arc::new(fresh);
+                    let jobs_after = config.backup_jobs.iter().filter(|j| j.enabled).count();
+                    let interval = cycle_interval(&config);
+                    if jobs_after != jobs_before || interval != min_interval {
+                        app_state.add_log("INFO", &format!(
+                            "Configuration reloaded: {} enabled job(s), interval {} seconds",
+                            jobs_after,
+                            interval
+                        )).await;
+                    }
+                    min_interval = interval;
+                    app_state.config_summary.write().await.jobs = config.backup_jobs.clone();
+                }
+                Err(e) => {
+                    app_state.add_log("WARN", &format!("Failed to reload configuration, keeping previous: {}", e)).await;
+                }
+            }
         } else {
             app_state.update_scheduler(SchedulerStatus {
                 running: true,
@@ -73,61 +116,211 @@ pub async fn run_scheduler(config: Arc<AppConfig>, shutdown: Arc<AtomicUsize>, a
                 interval_secs: min_interval,
                 connection_name: config.backup_jobs.first().map(|j| j.db_config_name.clone()),
                 database_count: config.backup_jobs.iter().map(|j| j.databases.len()).sum(),
+                progress: None,
+                current_database: None,
             }).await;
         }
         first_run = false;
 
-        if config.backup_jobs.is_empty() {
+        retry_uploads(&config, &app_state).await;
+        run_due_jobs(&config, min_interval, &shutdown, &app_state, &mut state).await;
+    }
+
+    crate::database::close_shared_pools().await;
+    app_state.add_log("INFO", "Scheduler stopped").await;
+    events::emit(Event::SchedulerStopped);
+}
+
+/// A single scheduler cycle, for setups where cron handles the timing: retries pending
+/// uploads and runs the jobs that are due according to the saved last-run times.
+/// Returns false if any backup failed.
+pub async fn run_scheduler_once(config: &AppConfig, shutdown: Arc<AtomicUsize>, app_state: Arc<AppState>) -> bool {
+    app_state.add_log("INFO", "Running one scheduler cycle").await;
+    check_startup(config, &app_state).await;
+    let mut state = load_schedule_state(&app_state);
+    retry_uploads(config, &app_state).await;
+    let success = run_due_jobs(config, cycle_interval(config), &shutdown, &app_state, &mut state).await;
+    crate::database::close_shared_pools().await;
+    success
+}
+
+async fn check_startup(config: &AppConfig, app_state: &AppState) {
+    for warning in crate::config::validate(config) {
+        app_state.add_log("WARN", &warning).await;
+    }
+    if let Err(e) = crate::config::check_writable(&config.local_backup_dir) {
+        app_state.add_log("WARN", &format!(
+            "Backup directory {:?} is not writable: {}",
+            config.local_backup_dir, e
+        )).await;
+    }
+
+    if config.backup_jobs.is_empty() {
+        app_state.add_log("WARN", &format!(
+            "No backup jobs configured. Scheduler will check for new jobs every {} seconds.",
+            config.idle_interval_secs
+        )).await;
+    }
+
+    crate::upload::health::reset();
+    let uploaders = crate::upload::create_uploaders(&config.upload);
+    for (name, error) in crate::upload::health::check_uploaders(&config.upload, &uploaders).await {
+        match error {
+            None => app_state.add_log("INFO", &format!("Uploader {} is healthy", name)).await,
+            Some(e) if config.upload.disable_failing => {
+                app_state.add_log("WARN", &format!("Uploader {} disabled: {}", name, e)).await
+            }
+            Some(e) => app_state.add_log("WARN", &format!("Uploader {} is failing: {}", name, e)).await,
+        }
+    }
+    let disabled = config.backup_jobs.iter().filter(|j| !j.enabled).count();
+    if disabled > 0 {
+        app_state.add_log("INFO", &format!("{} disabled backup job(s) will be skipped", disabled)).await;
+    }
+}
+
+fn load_schedule_state(app_state: &AppState) -> ScheduleState {
+    let state = ScheduleState::load();
+    for (job_key, at) in state.runs() {
+        app_state.record_job_run(job_key, *at);
+    }
+    state
+}
+
+async fn retry_uploads(config: &AppConfig, app_state: &AppState) {
+    let retried = crate::upload::retry_pending_uploads(&config.upload, Verbosity::Quiet).await;
+    if retried.uploaded > 0 || retried.remaining > 0 {
+        app_state.add_log("INFO", &format!(
+            "Retried pending uploads: {} uploaded, {} still pending",
+            retried.uploaded,
+            retried.remaining
+        )).await;
+    }
+}
+
+/// Runs every job whose interval has elapsed since its last run. Returns false if any
+/// backup failed.
+async fn run_due_jobs(
+    config: &AppConfig,
+    min_interval: u64,
+    shutdown: &AtomicUsize,
+    app_state: &Arc<AppState>,
+    state: &mut ScheduleState,
+) -> bool {
+    if config.backup_jobs.is_empty() {
+        return true;
+    }
+
+    let now = Utc::now();
+    if config.combine_jobs {
+        // A config change wakes the scheduler early; only run once the interval has elapsed.
+        if !state.is_due(crate::backup::job::COMBINED_CONNECTION_NAME, min_interval, now) {
+            return true;
+        }
+        app_state.add_log("INFO", "Executing full backup of all jobs into a single archive").await;
+        app_state.begin_operation(crate::backup::job::COMBINED_CONNECTION_NAME, config.combined_timeout());
+        let databases = crate::backup::job::combined_databases(config);
+        let queued = app_state.jobs.enqueue(crate::backup::job::COMBINED_CONNECTION_NAME, &databases);
+        let run = |token| crate::backup::job::execute_combined_backup(
+            config,
+            Verbosity::Quiet,
+            Some(progress_callback(app_state)),
+            Some(token),
+        );
+        let result = app_state.jobs.run(queued, run, |r: &BackupResult| r.success).await;
+        app_state.finish_operation();
+        let Some(result) = result else {
+            app_state.add_log("INFO", "Skipping cancelled full backup").await;
+            return true;
+        };
+        let success = result.success;
+        record_result(app_state, result).await;
+        state.record(crate::backup::job::COMBINED_CONNECTION_NAME, now);
+        return success;
+    }
+
+    let mut all_ok = true;
+    let mut seen_jobs = std::collections::HashSet::new();
+    for job in config.enabled_jobs_by_priority() {
+        if shutdown.load(Ordering::Relaxed) > 0 {
+            break;
+        }
+        let job_key = job.key();
+        if !seen_jobs.insert(job_key.clone()) {
+            app_state.add_log("WARN", &format!("Skipping duplicate backup job for {}", job.db_config_name)).await;
             continue;
         }
 
-        let now = std::time::Instant::now();
-        for job in &config.backup_jobs {
-            let job_key = format!("{}:{:?}", job.db_config_name, job.databases);
-            let interval_secs = job.schedule.as_seconds();
-
-            let should_run = match last_run.get(&job_key) {
-                Some(last) => now.duration_since(*last).as_secs() >= interval_secs,
-                None => true,
-            };
-
-            if should_run {
-                app_state.add_log("INFO", &format!("Executing backup job for {}", job.db_config_name)).await;
-                if let Some(db_config) = config.databases.iter().find(|d| d.name == job.db_config_name) {
-                    let result = crate::backup::job::execute_job_backup_silent(&config, db_config, &job.databases).await;
-                    app_state.add_backup_entry(BackupEntry {
-                        timestamp: Utc::now(),
-                        connection_name: result.connection_name.clone(),
-                        databases: result.databases.clone(),
-                        success: result.success,
-                        file_size: result.file_size.unwrap_or(0),
-                        duration_secs: result.duration_secs,
-                        error: result.error.clone(),
-                    }).await;
-                    
-                    if result.success {
-                        app_state.add_log("INFO", &format!(
-                            "Backup of {} ({} databases) completed: {:.2} MB in {} sec",
-                            result.connection_name,
-                            result.databases.len(),
-                            result.file_size.unwrap_or(0) as f64 / 1024.0 / 1024.0,
-                            result.duration_secs
-                        )).await;
-                    } else {
-                        app_state.add_log("ERROR", &format!(
-                            "Backup of {} failed: {}",
-                            result.connection_name,
-                            result.error.unwrap_or_default()
-                        )).await;
-                    }
-                } else {
-                    app_state.add_log("WARN", &format!("Database config '{}' not found", job.db_config_name)).await;
+        if state.is_due(&job_key, job.schedule.as_seconds(), now) {
+            app_state.add_log("INFO", &format!("Executing backup job for {}", job.db_config_name)).await;
+            if let Some(db_config) = config.databases.iter().find(|d| d.name == job.db_config_name) {
+                app_state.begin_operation(&job.db_config_name, config.job_timeout(job));
+                let queued = app_state.jobs.enqueue(&job.db_config_name, &job.databases);
+                let run = |token| crate::backup::job::execute_job_backup_with_progress(
+                    config,
+                    db_config,
+                    job,
+                    progress_callback(app_state),
+                    Some(token),
+                );
+                let results = app_state.jobs.run(queued, run, |r: &Vec<BackupResult>| crate::backup::job::all_succeeded(r)).await;
+                app_state.finish_operation();
+                let Some(results) = results else {
+                    app_state.add_log("INFO", &format!("Skipping cancelled backup job for {}", job.db_config_name)).await;
+                    continue;
+                };
+                all_ok &= crate::backup::job::all_succeeded(&results);
+                for result in results {
+                    record_result(app_state, result).await;
                 }
-
-                last_run.insert(job_key, now);
+            } else {
+                all_ok = false;
+                app_state.add_log("WARN", &format!("Database config '{}' not found", job.db_config_name)).await;
             }
+
+            app_state.record_job_run(&job_key, now);
+            state.record(&job_key, now);
         }
     }
+    all_ok
+}
 
-    app_state.add_log("INFO", "Scheduler stopped").await;
+fn progress_callback(app_state: &Arc<AppState>) -> ProgressCallback {
+    let state = app_state.clone();
+    Arc::new(move |progress| state.update_operation(&progress))
 }
+
+async fn record_result(app_state: &AppState, result: BackupResult) {
+    app_state.add_backup_entry(BackupEntry {
+        timestamp: Utc::now(),
+        connection_name: result.connection_name.clone(),
+        databases: result.databases.clone(),
+        success: result.success,
+        file_size: result.file_size.unwrap_or(0),
+        duration_secs: result.duration_secs,
+        error: result.error.clone(),
+        tags: result.tags.clone(),
+        uploads: result.uploads.clone(),
+        file_path: result.file_path.as_ref().map(|p| p.to_string_lossy().to_string()),
+        pinned: false,
+    }).await;
+    if let Some(pending) = &result.pending {
+        pending.clear();
+    }
+
+    for (db_name, warning) in &result.db_warnings {
+        app_state.add_log("WARN", &format!("{}: {}", db_name, warning)).await;
+    }
+
+    if result.success && result.file_path.is_none() {
+        app_state.add_log("INFO", &format!(
+            "{}: {}",
+            result.summary_line(),
+            result.skipped.join(", ")
+        )).await;
+    } else if result.success {
+        app_state.add_log("INFO", &result.summary_line()).await;
+    } else {
+        app_state.add_log("ERROR", &result.summary_line()).await;
+    }
+}
@@ -0,0 +1,37 @@
+use crate::config::{self, default_shutdown_timeout_secs};
+use crate::web::AppState;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::{sleep, Instant};
+use tracing::{info, warn};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Drains backups on the first Ctrl+C: no new job starts, and running ones get
+/// `shutdown_timeout_secs` to finish before they are cancelled, which removes their
+/// partial dumps. Forced exits on the second Ctrl+C are left to the signal handler.
+pub async fn drain_on_signal(signals: Arc<AtomicUsize>, app_state: Arc<AppState>) {
+    while signals.load(Ordering::Relaxed) == 0 {
+        sleep(POLL_INTERVAL).await;
+    }
+    app_state.jobs.close();
+    if !app_state.jobs.has_running() {
+        return;
+    }
+
+    let timeout_secs = config::load()
+        .map(|c| c.shutdown_timeout_secs)
+        .unwrap_or_else(|_| default_shutdown_timeout_secs());
+    info!("Waiting up to {} seconds for running backups to finish", timeout_secs);
+    let deadline = Instant::now() + Duration::from_secs(timeout_secs);
+    while app_state.jobs.has_running() {
+        if Instant::now() >= deadline {
+            let cancelled = app_state.jobs.cancel_running();
+            warn!("Shutdown timeout reached, cancelled {} running backup(s)", cancelled);
+            app_state.add_log("WARN", "Shutdown timeout reached, running backups cancelled").await;
+            return;
+        }
+        sleep(POLL_INTERVAL).await;
+    }
+}
@@ -0,0 +1,48 @@
+use sd_notify::NotifyState;
+use std::sync::OnceLock;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::debug;
+
+static INTERVAL: OnceLock<Option<Duration>> = OnceLock::new();
+
+/// How often to send heartbeats: half the `WatchdogSec=` systemd passed in `WATCHDOG_USEC`,
+/// or `None` when the process isn't supervised by a watchdog.
+pub fn interval() -> Option<Duration> {
+    *INTERVAL.get_or_init(|| {
+        let mut usec = 0;
+        sd_notify::watchdog_enabled(false, &mut usec).then(|| Duration::from_micros(usec / 2))
+    })
+}
+
+/// Tells systemd the service is up (`Type=notify`). Does nothing outside systemd.
+pub fn notify_ready() {
+    if let Err(e) = sd_notify::notify(false, &[NotifyState::Ready]) {
+        debug!("Failed to notify systemd: {}", e);
+    }
+}
+
+/// Stops the heartbeat task when dropped.
+pub struct Heartbeat(JoinHandle<()>);
+
+impl Drop for Heartbeat {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// Sends `WATCHDOG=1` every [`interval`] from a task of its own until the returned value
+/// is dropped, so heartbeats continue through a single large table, compression or an
+/// upload. `None` outside a systemd watchdog.
+pub fn spawn_heartbeat() -> Option<Heartbeat> {
+    let interval = interval()?;
+    Some(Heartbeat(tokio::spawn(async move {
+        let mut ticks = tokio::time::interval(interval);
+        loop {
+            ticks.tick().await;
+            if let Err(e) = sd_notify::notify(false, &[NotifyState::Watchdog]) {
+                debug!("Failed to send watchdog heartbeat: {}", e);
+            }
+        }
+    })))
+}
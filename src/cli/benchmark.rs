@@ -0,0 +1,167 @@
+use super::output;
+use crate::backup::compression::compress_multiple_to_zip_with_level;
+use crate::config;
+use crate::database::create_driver;
+use crate::error::{BackupError, Result};
+use crate::log::Verbosity;
+use console::style;
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+const LEVELS: [i64; 5] = [0, 1, 3, 6, 9];
+
+/// A level is "as good as" the smallest result when within this fraction of its size.
+const SIZE_TOLERANCE: f64 = 0.02;
+
+struct LevelResult {
+    level: i64,
+    size: u64,
+    elapsed: Duration,
+}
+
+fn method_name(level: i64) -> &'static str {
+    if level == 0 {
+        "stored"
+    } else {
+        "deflate"
+    }
+}
+
+/// Picks the fastest level whose output is within `SIZE_TOLERANCE` of the smallest archive.
+fn recommend(results: &[LevelResult]) -> Option<&LevelResult> {
+    let smallest = results.iter().map(|r| r.size).min()?;
+    let limit = smallest as f64 * (1.0 + SIZE_TOLERANCE);
+    results
+        .iter()
+        .filter(|r| r.size as f64 <= limit)
+        .min_by_key(|r| r.elapsed)
+}
+
+pub async fn run_benchmark(connection: &str, database: &str) -> Result<()> {
+    let config = config::load()?;
+    let db_config = config
+        .databases
+        .iter()
+        .find(|d| d.name == connection)
+        .ok_or_else(|| BackupError::Config(format!("Unknown connection '{}'", connection)))?;
+    let quiet = output::is_quiet();
+
+    let work_dir = std::env::temp_dir().join(format!("tlm_benchmark_{}", std::process::id()));
+    fs::create_dir_all(&work_dir)?;
+    let sql_path = work_dir.join(format!("{}.sql", database));
+
+    if !quiet {
+        println!("{}", style(format!("Dumping {}/{}...", connection, database)).yellow());
+    }
+    let driver = create_driver(db_config)?;
+    let dump_start = Instant::now();
+    let writer = BufWriter::new(File::create(&sql_path)?);
+    let dumped = driver.dump_database_silent(database, Box::new(writer), Verbosity::Quiet, None).await;
+    let _ = driver.close().await;
+    if let Err(e) = dumped {
+        let _ = fs::remove_dir_all(&work_dir);
+        return Err(e);
+    }
+    let dump_elapsed = dump_start.elapsed();
+    let sql_size = fs::metadata(&sql_path)?.len();
+
+    let sources: Vec<(PathBuf, String)> = vec![(sql_path, format!("{}.sql", database))];
+    let mut results = Vec::new();
+    for level in LEVELS {
+        let zip_path = work_dir.join(format!("level_{}.zip", level));
+        let start = Instant::now();
+        let compressed = compress_multiple_to_zip_with_level(&sources, &zip_path, true, level, None);
+        let elapsed = start.elapsed();
+        if let Err(e) = compressed {
+            let _ = fs::remove_dir_all(&work_dir);
+            return Err(e);
+        }
+        results.push(LevelResult {
+            level,
+            size: fs::metadata(&zip_path)?.len(),
+            elapsed,
+        });
+        let _ = fs::remove_file(&zip_path);
+    }
+    let _ = fs::remove_dir_all(&work_dir);
+
+    let recommended = recommend(&results);
+    if quiet {
+        println!(
+            "{}",
+            serde_json::json!({
+                "connection": connection,
+                "database": database,
+                "sql_size": sql_size,
+                "dump_ms": dump_elapsed.as_millis() as u64,
+                "results": results
+                    .iter()
+                    .map(|r| serde_json::json!({
+                        "level": r.level,
+                        "method": method_name(r.level),
+                        "size": r.size,
+                        "ms": r.elapsed.as_millis() as u64,
+                    }))
+                    .collect::<Vec<_>>(),
+                "recommended_level": recommended.map(|r| r.level),
+                "current_level": config.compression_level,
+            })
+        );
+        return Ok(());
+    }
+
+    println!(
+        "\nDump: {:.2} MB in {:.1}s",
+        sql_size as f64 / 1024.0 / 1024.0,
+        dump_elapsed.as_secs_f64()
+    );
+    println!("\n{}", style("Level  Method    Size (MB)  Ratio   Time").cyan().bold());
+    for r in &results {
+        let ratio = if sql_size > 0 {
+            r.size as f64 / sql_size as f64 * 100.0
+        } else {
+            0.0
+        };
+        println!(
+            "{:>5}  {:<8}  {:>9.2}  {:>5.1}%  {:.2}s",
+            r.level,
+            method_name(r.level),
+            r.size as f64 / 1024.0 / 1024.0,
+            ratio,
+            r.elapsed.as_secs_f64()
+        );
+    }
+
+    if let Some(r) = recommended {
+        println!(
+            "\n{} compression_level = {} (currently {})",
+            style("Recommended:").green().bold(),
+            r.level,
+            config.compression_level
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recommend_prefers_faster_level_of_similar_size() {
+        let result = |level, size, ms| LevelResult {
+            level,
+            size,
+            elapsed: Duration::from_millis(ms),
+        };
+        let results = vec![
+            result(0, 1000, 5),
+            result(1, 300, 20),
+            result(6, 250, 60),
+            result(9, 248, 200),
+        ];
+        assert_eq!(recommend(&results).map(|r| r.level), Some(6));
+    }
+}
@@ -0,0 +1,47 @@
+use super::output;
+use crate::config::{self, DatabaseConfig};
+use crate::database::option_file::DEFAULT_OPTION_GROUP;
+use crate::error::{BackupError, Result};
+use console::style;
+use std::path::PathBuf;
+
+/// Adds a connection without prompting. An existing connection of that name is an
+/// error unless `force` is set, so provisioning scripts can be re-run safely with it.
+pub fn add_connection(
+    name: &str,
+    url: Option<&str>,
+    option_file: Option<PathBuf>,
+    option_group: Option<String>,
+    force: bool,
+) -> Result<()> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(BackupError::Config("Connection name cannot be empty".to_string()));
+    }
+    let mut config = config::load()?;
+    if !force && config.databases.iter().any(|d| d.name == name) {
+        return Err(BackupError::Config(format!(
+            "Connection '{}' already exists; pass --force to replace it",
+            name
+        )));
+    }
+
+    let db_config = match url {
+        Some(url) => DatabaseConfig::from_url(name, url)
+            .map_err(|e| BackupError::Config(format!("Invalid connection URL: {}", e)))?,
+        None => DatabaseConfig {
+            name: name.to_string(),
+            option_file,
+            option_group: Some(option_group.unwrap_or_else(|| DEFAULT_OPTION_GROUP.to_string())),
+            ..DatabaseConfig::default()
+        },
+    };
+    let replaced = config.upsert_database(db_config);
+    config::save(&config)?;
+
+    if !output::is_quiet() {
+        let action = if replaced { "replaced" } else { "added" };
+        println!("{}", style(format!("Database connection '{}' {}.", name, action)).green());
+    }
+    Ok(())
+}
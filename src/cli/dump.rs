@@ -0,0 +1,32 @@
+use crate::config;
+use crate::database::create_driver;
+use crate::error::{BackupError, Result};
+use crate::log::Verbosity;
+use std::io::{self, BufWriter};
+use tracing::info;
+
+/// Streams a plain SQL dump to stdout, bypassing the archive and upload pipeline.
+/// Logs go to stderr, so stdout only ever carries SQL.
+pub async fn dump_to_stdout(connection: &str, database: &str) -> Result<()> {
+    let config = config::load()?;
+    let db_config = config
+        .databases
+        .iter()
+        .find(|d| d.name == connection)
+        .ok_or_else(|| BackupError::Config(format!("Unknown connection '{}'", connection)))?;
+
+    let driver = create_driver(db_config)?;
+    let writer = BufWriter::new(io::stdout());
+    let dumped = driver
+        .dump_database_silent(database, Box::new(writer), Verbosity::Quiet, None)
+        .await;
+    let _ = driver.close().await;
+    let report = dumped?;
+    info!(
+        "Dumped {}/{} to stdout ({} warning(s))",
+        connection,
+        database,
+        report.warnings.len()
+    );
+    Ok(())
+}
@@ -1,11 +1,19 @@
-use crate::backup::run_scheduler;
-use crate::config::{self, AppConfig};
+use super::output;
+use crate::backup::restore::{self, ArchiveEntry};
+use crate::backup::retention;
+use crate::backup::queue::JobState;
+use crate::backup::job::all_succeeded;
+use crate::backup::{run_scheduler, BackupResult};
+use crate::config::{self, AppConfig, BackupJob, DatabaseConfig};
 use crate::database::create_driver;
+use crate::database::pattern::resolve_databases;
 use crate::error::{BackupError, Result};
-use crate::upload::{BackupUploader, DiscordUploader};
-use crate::web::{AppState, BackupEntry, ConfigSummary, SchedulerStatus};
-use console::style;
-use dialoguer::Select;
+use crate::upload::{
+    proxy_summary, retry_pending_uploads, BackupMetadata, BackupUploader, DiscordUploader, PendingUploads,
+};
+use crate::web::{AppState, BackupEntry, ConfigSummary, ConnectionCheck, HistoryQuery, SchedulerStatus};
+use console::{style, Term};
+use dialoguer::{Confirm, Input, MultiSelect, Password, Select};
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
 use tokio::sync::mpsc;
@@ -14,18 +22,33 @@ use tokio::task::JoinHandle;
 #[derive(Debug, Clone, Copy, PartialEq)]
 enum MenuOption {
     RunBackupNow,
+    StatusOverview,
+    RetryUploads,
+    JobQueue,
+    RestoreBackup,
+    PinBackups,
     SchedulerMenu,
     WebDashboardMenu,
     EditConfiguration,
     TestDatabaseConnection,
     TestDiscordUpload,
+    OpenBackupDirectory,
+    SwitchProfile,
     Quit,
 }
 
 impl MenuOption {
-    fn display(&self, scheduler_running: bool, web_running: bool) -> String {
+    fn display(&self, scheduler_running: bool, web_running: bool, active_jobs: usize) -> String {
         match self {
             MenuOption::RunBackupNow => "Run backup now (all jobs)".to_string(),
+            MenuOption::StatusOverview => "Status overview".to_string(),
+            MenuOption::RetryUploads => format!(
+                "Retry pending uploads [{}]",
+                PendingUploads::load().len()
+            ),
+            MenuOption::JobQueue => format!("Job queue [{} active]", active_jobs),
+            MenuOption::RestoreBackup => "Restore from backup".to_string(),
+            MenuOption::PinBackups => "Pin or unpin backups".to_string(),
             MenuOption::SchedulerMenu => {
                 if scheduler_running {
                     format!("Scheduler [{}]", style("RUNNING").green())
@@ -43,6 +66,11 @@ impl MenuOption {
             MenuOption::EditConfiguration => "Edit configuration".to_string(),
             MenuOption::TestDatabaseConnection => "Test database connection".to_string(),
             MenuOption::TestDiscordUpload => "Test Discord upload".to_string(),
+            MenuOption::OpenBackupDirectory => "Open backup directory".to_string(),
+            MenuOption::SwitchProfile => format!(
+                "Switch profile [{}]",
+                style(config::active_profile().unwrap_or_else(|| "default".to_string())).cyan()
+            ),
             MenuOption::Quit => "Quit".to_string(),
         }
     }
@@ -72,6 +100,7 @@ enum WebOption {
     Start,
     Stop,
     ViewLogs,
+    ChangeCredentials,
     Back,
 }
 
@@ -81,6 +110,7 @@ impl std::fmt::Display for WebOption {
             WebOption::Start => write!(f, "Start web dashboard"),
             WebOption::Stop => write!(f, "Stop web dashboard"),
             WebOption::ViewLogs => write!(f, "View dashboard info"),
+            WebOption::ChangeCredentials => write!(f, "Change credentials"),
             WebOption::Back => write!(f, "Back to main menu"),
         }
     }
@@ -90,10 +120,12 @@ impl std::fmt::Display for WebOption {
 enum EditOption {
     DatabaseConnection,
     SelectDatabases,
+    ToggleJobs,
     ChangeSchedule,
     UploadSettings,
     WebDashboard,
     BackupDirectory,
+    Timezone,
     Back,
 }
 
@@ -102,28 +134,90 @@ impl std::fmt::Display for EditOption {
         match self {
             EditOption::DatabaseConnection => write!(f, "Add/Edit database connection"),
             EditOption::SelectDatabases => write!(f, "Select databases to backup"),
+            EditOption::ToggleJobs => write!(f, "Enable/disable backup jobs"),
             EditOption::ChangeSchedule => write!(f, "Change backup schedule"),
             EditOption::UploadSettings => write!(f, "Configure Discord upload"),
             EditOption::WebDashboard => write!(f, "Configure web dashboard"),
             EditOption::BackupDirectory => write!(f, "Change backup directory"),
+            EditOption::Timezone => write!(f, "Change display timezone"),
             EditOption::Back => write!(f, "Back to main menu"),
         }
     }
 }
 
-fn display_header() {
+fn display_header(update_notice: Option<&str>) {
     println!();
     println!("{}", style("╔════════════════════════════════════════╗").cyan());
     println!("{}", style("║     TLM Database Backup Manager        ║").cyan());
     println!("{}", style("╚════════════════════════════════════════╝").cyan());
+    if let Some(version) = update_notice {
+        println!(
+            "{}",
+            style(format!(
+                "Version {} is available (running {})",
+                version,
+                env!("CARGO_PKG_VERSION")
+            ))
+            .yellow()
+        );
+    }
     println!();
 }
 
-fn display_summary(config: &AppConfig, scheduler_running: bool, web_running: bool) {
+/// Connection test results younger than this are reused instead of re-probing.
+const CONNECTION_CHECK_TTL_SECS: i64 = 300;
+
+const MIN_LOG_VIEW_LINES: usize = 15;
+
+/// Lines taken by the live log view's header, status block and footer.
+const LOG_VIEW_CHROME_LINES: usize = 13;
+
+/// Number of log lines that fit in the terminal below the status block, never fewer than 15.
+fn log_view_lines() -> usize {
+    let (rows, _) = Term::stdout().size();
+    (rows as usize)
+        .saturating_sub(LOG_VIEW_CHROME_LINES)
+        .max(MIN_LOG_VIEW_LINES)
+}
+
+fn format_age(secs: i64) -> String {
+    match secs {
+        s if s < 60 => format!("{}s", s),
+        s if s < 3600 => format!("{}m", s / 60),
+        s => format!("{}h", s / 3600),
+    }
+}
+
+fn describe_check(check: &ConnectionCheck) -> String {
+    let status = if check.is_ok() {
+        style("OK".to_string()).green()
+    } else {
+        style("FAILED".to_string()).red()
+    };
+    format!("last verified {} ago ({})", format_age(check.age_secs()), status)
+}
+
+async fn probe_connection(db_config: &DatabaseConfig, app_state: &AppState) -> ConnectionCheck {
+    let error = match create_driver(db_config) {
+        Ok(driver) => {
+            let error = driver.test_connection().await.err().map(|e| e.to_string());
+            let _ = driver.close().await;
+            error
+        }
+        Err(e) => Some(e.to_string()),
+    };
+    app_state.record_connection_check(&db_config.name, error)
+}
+
+fn display_summary(config: &AppConfig, app_state: &AppState, scheduler_running: bool, web_running: bool) {
     let db_count = config.databases.len();
     let job_count = config.backup_jobs.len();
+    let disabled_count = config.backup_jobs.iter().filter(|j| !j.enabled).count();
 
     println!("{}", style("Current Configuration:").bold());
+    if let Some(profile) = config::active_profile() {
+        println!("  Profile: {}", style(profile).cyan());
+    }
     println!(
         "  Database connections: {}",
         if db_count > 0 {
@@ -132,12 +226,27 @@ fn display_summary(config: &AppConfig, scheduler_running: bool, web_running: boo
             style("None".to_string()).red()
         }
     );
+    for db_config in &config.databases {
+        println!(
+            "    {}: {}",
+            db_config.name,
+            match app_state.connection_check(&db_config.name) {
+                Some(check) => describe_check(&check),
+                None => style("not verified".to_string()).dim().to_string(),
+            }
+        );
+    }
     println!(
-        "  Backup jobs: {}",
+        "  Backup jobs: {}{}",
         if job_count > 0 {
             style(job_count.to_string()).green()
         } else {
             style("None".to_string()).red()
+        },
+        if disabled_count > 0 {
+            style(format!(" ({} disabled)", disabled_count)).yellow().to_string()
+        } else {
+            String::new()
         }
     );
     println!(
@@ -198,7 +307,11 @@ impl BackgroundServices {
 }
 
 pub async fn run_menu(shutdown: Arc<AtomicUsize>, app_state: Arc<AppState>) -> Result<()> {
-    let mut config = config::load()?;
+    let mut config = match config::load() {
+        Ok(config) => config,
+        Err(BackupError::Serialization(e)) => super::recovery::recover_malformed_config(e)?,
+        Err(e) => return Err(e),
+    };
     let mut services = BackgroundServices::new();
     if config.databases.is_empty() {
         println!(
@@ -209,6 +322,18 @@ pub async fn run_menu(shutdown: Arc<AtomicUsize>, app_state: Arc<AppState>) -> R
         config::save(&config)?;
     }
     update_config_summary(&config, &app_state).await;
+    open_history_store(&config, &app_state).await;
+    for warning in config::validate(&config) {
+        println!("{} {}", style("⚠").yellow(), warning);
+    }
+    if let Err(e) = config::check_writable(&config.local_backup_dir) {
+        println!("{} Backup directory {:?} is not writable: {}", style("⚠").yellow(), config.local_backup_dir, e);
+    }
+    let update_notice = if config.check_updates {
+        super::update::check_for_update(&config.upload.http).await
+    } else {
+        None
+    };
 
     loop {
         if shutdown.load(Ordering::Relaxed) > 0 {
@@ -218,22 +343,30 @@ pub async fn run_menu(shutdown: Arc<AtomicUsize>, app_state: Arc<AppState>) -> R
             break;
         }
 
-        display_header();
-        display_summary(&config, services.is_scheduler_running(), services.is_web_running());
+        display_header(update_notice.as_deref());
+        display_summary(&config, &app_state, services.is_scheduler_running(), services.is_web_running());
 
         let menu_items = vec![
             MenuOption::RunBackupNow,
+            MenuOption::StatusOverview,
+            MenuOption::RetryUploads,
+            MenuOption::JobQueue,
+            MenuOption::RestoreBackup,
+            MenuOption::PinBackups,
             MenuOption::SchedulerMenu,
             MenuOption::WebDashboardMenu,
             MenuOption::EditConfiguration,
             MenuOption::TestDatabaseConnection,
             MenuOption::TestDiscordUpload,
+            MenuOption::OpenBackupDirectory,
+            MenuOption::SwitchProfile,
             MenuOption::Quit,
         ];
 
+        let active_jobs = app_state.jobs.snapshot().iter().filter(|j| j.state.is_active()).count();
         let display_items: Vec<String> = menu_items
             .iter()
-            .map(|m| m.display(services.is_scheduler_running(), services.is_web_running()))
+            .map(|m| m.display(services.is_scheduler_running(), services.is_web_running(), active_jobs))
             .collect();
 
         let selection = match Select::new()
@@ -251,26 +384,64 @@ pub async fn run_menu(shutdown: Arc<AtomicUsize>, app_state: Arc<AppState>) -> R
             MenuOption::RunBackupNow => {
                 run_backup_now(&config, app_state.clone()).await;
             }
+            MenuOption::StatusOverview => {
+                status_overview(&config, &app_state, services.is_scheduler_running()).await;
+            }
+            MenuOption::RetryUploads => {
+                retry_uploads(&config).await;
+            }
+            MenuOption::JobQueue => {
+                job_queue(&app_state).await;
+            }
+            MenuOption::RestoreBackup => {
+                if let Err(e) = restore_backup(&config).await {
+                    println!("{}: {}", style("Restore failed").red(), e);
+                }
+                println!("\nPress Enter to continue...");
+                let _ = std::io::stdin().read_line(&mut String::new());
+            }
+            MenuOption::PinBackups => {
+                if let Err(e) = pin_backups(&config) {
+                    println!("{}: {}", style("Error").red(), e);
+                }
+            }
             MenuOption::SchedulerMenu => {
                 scheduler_menu(&config, &mut services, app_state.clone()).await;
             }
             MenuOption::WebDashboardMenu => {
+                reload_web_credentials(&mut config);
                 web_dashboard_menu(&mut config, &mut services, app_state.clone()).await;
             }
             MenuOption::EditConfiguration => {
+                reload_web_credentials(&mut config);
                 if let Err(e) = edit_configuration(&mut config).await {
                     println!("{}: {}", style("Error").red(), e);
                 } else {
                     let _ = config::save(&config);
                     update_config_summary(&config, &app_state).await;
+                    app_state.set_credentials(config.web.username.clone(), config.web.password.clone()).await;
+                    app_state.set_api_token(config.web.api_token.clone()).await;
+                    app_state.notify_config_changed();
+                    if services.is_scheduler_running() {
+                        println!("{}", style("Running scheduler will reload the new configuration.").dim());
+                    }
+                    check_uploaders(&config).await;
                 }
             }
             MenuOption::TestDatabaseConnection => {
-                test_database_connection(&config).await;
+                test_database_connection(&config, &app_state).await;
             }
             MenuOption::TestDiscordUpload => {
                 test_discord_upload(&config).await;
             }
+            MenuOption::OpenBackupDirectory => {
+                open_backup_directory(&config);
+            }
+            MenuOption::SwitchProfile => {
+                if let Err(e) = switch_profile(&mut config, &mut services, &app_state).await {
+                    println!("{}: {}", style("Error").red(), e);
+                }
+            }
             MenuOption::Quit => {
                 if services.is_scheduler_running() {
                     println!("{}", style("Stopping scheduler...").yellow());
@@ -282,18 +453,118 @@ pub async fn run_menu(shutdown: Arc<AtomicUsize>, app_state: Arc<AppState>) -> R
         }
     }
 
+    if let Some(handle) = services.scheduler_handle.take() {
+        if !handle.is_finished() {
+            println!("{}", style("Waiting for the running backup to finish...").yellow());
+            let _ = handle.await;
+        }
+    }
+
     Ok(())
 }
 
-async fn update_config_summary(config: &AppConfig, app_state: &Arc<AppState>) {
+/// Picks up dashboard credentials changed through `POST /api/credentials`, so the
+/// menu's copy of the config doesn't restore the old ones when it is saved.
+fn reload_web_credentials(config: &mut AppConfig) {
+    if let Ok(saved) = config::load() {
+        config.web.username = saved.web.username;
+        config.web.password = saved.web.password;
+    }
+}
+
+pub(super) async fn update_config_summary(config: &AppConfig, app_state: &Arc<AppState>) {
     app_state.update_config(ConfigSummary {
         database_connections: config.databases.len(),
         backup_jobs: config.backup_jobs.len(),
         discord_configured: config.upload.discord.is_some(),
         backup_directory: config.local_backup_dir.to_string_lossy().to_string(),
+        jobs: config.backup_jobs.clone(),
     }).await;
 }
 
+pub(super) async fn open_history_store(config: &AppConfig, app_state: &Arc<AppState>) {
+    if let Some(path) = config::history_db_path(config) {
+        if let Err(e) = app_state.open_history_store(&path).await {
+            println!("{}: {}", style("Failed to open history database").yellow(), e);
+        }
+    }
+    for notice in crate::backup::inventory::reconcile(config, app_state).await {
+        if output::is_quiet() {
+            eprintln!("{}", notice);
+        } else {
+            println!("{} {}", style("⚠").yellow(), notice);
+        }
+    }
+}
+
+async fn switch_profile(
+    config: &mut AppConfig,
+    services: &mut BackgroundServices,
+    app_state: &Arc<AppState>,
+) -> Result<()> {
+    let current = config::active_profile();
+    let mut choices = vec!["default".to_string()];
+    choices.extend(config::list_profiles().into_iter().filter(|p| p != "default"));
+    choices.push("Create new profile...".to_string());
+
+    let selection = Select::new()
+        .with_prompt("Select profile")
+        .items(&choices)
+        .default(0)
+        .interact_opt()
+        .map_err(|e| BackupError::Config(e.to_string()))?;
+    let idx = match selection {
+        Some(idx) => idx,
+        None => return Ok(()),
+    };
+
+    let profile = if idx == 0 {
+        None
+    } else if idx == choices.len() - 1 {
+        let name: String = Input::new()
+            .with_prompt("Profile name")
+            .interact_text()
+            .map_err(|e| BackupError::Config(e.to_string()))?;
+        config::validate_profile_name(&name)?;
+        Some(name)
+    } else {
+        Some(choices[idx].clone())
+    };
+
+    if profile == current {
+        return Ok(());
+    }
+
+    if services.is_scheduler_running() {
+        println!("{}", style("Stopping scheduler before switching profile...").yellow());
+        services.scheduler_shutdown.store(true, Ordering::SeqCst);
+    }
+
+    config::set_active_profile(profile)?;
+    *config = config::load()?;
+    if config.databases.is_empty() {
+        super::wizard::run_initial_setup(config).await?;
+        config::save(config)?;
+    }
+
+    app_state.reset_history().await;
+    app_state.clear_logs().await;
+    app_state.set_credentials(config.web.username.clone(), config.web.password.clone()).await;
+    app_state.set_api_token(config.web.api_token.clone()).await;
+    update_config_summary(config, app_state).await;
+    open_history_store(config, app_state).await;
+
+    println!(
+        "{}",
+        style(format!(
+            "Switched to profile '{}'",
+            config::active_profile().unwrap_or_else(|| "default".to_string())
+        ))
+        .green()
+    );
+    Ok(())
+}
+
 async fn scheduler_menu(config: &AppConfig, services: &mut BackgroundServices, app_state: Arc<AppState>) {
     loop {
         println!("\n{}", style("=== Scheduler ===").cyan().bold());
@@ -368,6 +639,8 @@ async fn scheduler_menu(config: &AppConfig, services: &mut BackgroundServices, a
                         interval_secs: 0,
                         connection_name: None,
                         database_count: 0,
+                        progress: None,
+                        current_database: None,
                     }).await;
                     
                     println!("{}", style("Scheduler stop signal sent!").green());
@@ -379,25 +652,31 @@ async fn scheduler_menu(config: &AppConfig, services: &mut BackgroundServices, a
                 loop {
                     print!("\x1B[2J\x1B[1;1H");
                     println!("{}", style("=== Live Scheduler Logs (press 'q' to exit) ===").cyan().bold());
-                    let scheduler = app_state.scheduler.read().await;
+                    let scheduler = app_state.scheduler_status().await;
                     println!("\n{}", style("Status:").cyan());
                     println!("  Running: {}", if scheduler.running { style("Yes").green() } else { style("No").dim() });
                     if let Some(ref next) = scheduler.next_run {
-                        println!("  Next run: {}", style(next.format("%Y-%m-%d %H:%M:%S UTC")).cyan());
+                        println!("  Next run: {}", style(config::format_local(next, "%Y-%m-%d %H:%M:%S %Z")).cyan());
                     }
                     println!("  Interval: {} seconds", scheduler.interval_secs);
                     if let Some(ref conn) = scheduler.connection_name {
                         println!("  Connection: {}", style(conn).cyan());
                     }
                     println!("  Databases: {}", scheduler.database_count);
-                    drop(scheduler);
+                    if let Some(progress) = scheduler.progress {
+                        println!(
+                            "  Progress: {} {}",
+                            style(format!("{:.0}%", progress * 100.0)).green(),
+                            style(scheduler.current_database.as_deref().unwrap_or("")).dim()
+                        );
+                    }
 
                     println!("\n{}", style("Recent Logs:").cyan());
                     let logs = app_state.scheduler_logs.read().await;
                     if logs.is_empty() {
                         println!("  {}", style("No logs yet").dim());
                     } else {
-                        for log in logs.iter().take(15) {
+                        for log in logs.iter().take(log_view_lines()) {
                             let level_style = match log.level.as_str() {
                                 "ERROR" => style(&log.level).red(),
                                 "WARN" => style(&log.level).yellow(),
@@ -405,7 +684,7 @@ async fn scheduler_menu(config: &AppConfig, services: &mut BackgroundServices, a
                             };
                             println!(
                                 "  {} [{}] {}",
-                                style(log.timestamp.format("%H:%M:%S")).dim(),
+                                style(config::format_local(&log.timestamp, "%H:%M:%S")).dim(),
                                 level_style,
                                 log.message
                             );
@@ -457,6 +736,7 @@ async fn web_dashboard_menu(config: &mut AppConfig, services: &mut BackgroundSer
             WebOption::Start,
             WebOption::Stop,
             WebOption::ViewLogs,
+            WebOption::ChangeCredentials,
             WebOption::Back,
         ];
 
@@ -480,20 +760,17 @@ async fn web_dashboard_menu(config: &mut AppConfig, services: &mut BackgroundSer
                     println!("{}", style("Web dashboard credentials not set. Please configure them first.").red());
                 } else {
                     app_state.set_credentials(config.web.username.clone(), config.web.password.clone()).await;
-                    app_state.update_config(ConfigSummary {
-                        database_connections: config.databases.len(),
-                        backup_jobs: config.backup_jobs.len(),
-                        discord_configured: config.upload.discord.is_some(),
-                        backup_directory: config.local_backup_dir.to_string_lossy().to_string(),
-                    }).await;
+                    app_state.set_api_token(config.web.api_token.clone()).await;
+                    update_config_summary(&config, &app_state).await;
 
                     let port = config.web.port;
+                    let web_config = config.web.clone();
                     let state = app_state.clone();
                     let running = services.web_running.clone();
                     running.store(true, Ordering::SeqCst);
                     
                     services.web_handle = Some(tokio::spawn(async move {
-                        crate::web::start_server(state, port).await;
+                        crate::web::start_server(state, web_config).await;
                         running.store(false, Ordering::SeqCst);
                     }));
                     
@@ -536,26 +813,221 @@ async fn web_dashboard_menu(config: &mut AppConfig, services: &mut BackgroundSer
                     let _ = std::io::stdin().read_line(&mut String::new());
                 }).await;
             }
+            WebOption::ChangeCredentials => {
+                if let Err(e) = change_web_credentials(config, &app_state).await {
+                    println!("{}: {}", style("Error").red(), e);
+                }
+            }
             WebOption::Back => break,
         }
     }
 }
 
+/// Updates the saved dashboard credentials and, if the dashboard is running, the
+/// credentials it accepts, without restarting it.
+async fn change_web_credentials(config: &mut AppConfig, app_state: &AppState) -> Result<()> {
+    let username: String = Input::new()
+        .with_prompt("Username")
+        .default(config.web.username.clone())
+        .interact_text()
+        .map_err(|e| BackupError::Config(e.to_string()))?;
+    let password: String = Password::new()
+        .with_prompt("New password")
+        .with_confirmation("Confirm password", "Passwords don't match")
+        .interact()
+        .map_err(|e| BackupError::Config(e.to_string()))?;
+
+    let mut draft = config.clone();
+    config::set_web_credentials(&mut draft, username.clone(), password.clone())?;
+    config::save(&draft)?;
+    *config = draft;
+    app_state.set_credentials(username, password).await;
+    println!("{}", style("Dashboard credentials updated.").green());
+    Ok(())
+}
+
+pub async fn run_once(app_state: Arc<AppState>, connection: Option<&str>, databases: &[String]) -> Result<bool> {
+    let config = config::load()?;
+    if config.databases.is_empty() {
+        return Err(BackupError::Config(
+            "No configuration found. Run without a command to start the setup wizard.".to_string(),
+        ));
+    }
+    let Some(connection) = connection else {
+        open_history_store(&config, &app_state).await;
+        let success = run_all_jobs(&config, &app_state).await;
+        crate::database::close_shared_pools().await;
+        return Ok(success);
+    };
+
+    let (db_config, jobs) = connection_jobs(&config, connection, databases).await?;
+    open_history_store(&config, &app_state).await;
+
+    let mut results = Vec::new();
+    for job in &jobs {
+        let queued = app_state.jobs.enqueue(&job.db_config_name, &job.databases);
+        let run = |token| crate::backup::execute_job_backup(&config, db_config, job, Some(token));
+        if let Some(job_results) = app_state.jobs.run(queued, run, |r: &Vec<BackupResult>| all_succeeded(r)).await {
+            results.extend(job_results);
+        }
+    }
+    crate::database::close_shared_pools().await;
+    Ok(report_results(&results, &app_state).await)
+}
+
+/// The enabled jobs of `connection`, narrowed to `databases` when any are given.
+/// Every requested database must exist on the server and belong to one of the jobs.
+async fn connection_jobs<'a>(
+    config: &'a AppConfig,
+    connection: &str,
+    databases: &[String],
+) -> Result<(&'a DatabaseConfig, Vec<BackupJob>)> {
+    let db_config = config
+        .databases
+        .iter()
+        .find(|d| d.name == connection)
+        .ok_or_else(|| BackupError::Config(format!("Unknown connection '{}'", connection)))?;
+    let jobs: Vec<&BackupJob> = config
+        .enabled_jobs_by_priority()
+        .into_iter()
+        .filter(|j| j.db_config_name == connection)
+        .collect();
+    if jobs.is_empty() {
+        return Err(BackupError::Config(format!("No enabled jobs for connection '{}'", connection)));
+    }
+    if databases.is_empty() {
+        return Ok((db_config, jobs.into_iter().cloned().collect()));
+    }
+
+    let driver = create_driver(db_config)?;
+    let available = driver.list_databases().await;
+    let _ = driver.close().await;
+    let available = available?;
+    if let Some(missing) = databases.iter().find(|db| !available.contains(db)) {
+        return Err(BackupError::Config(format!(
+            "Database '{}' does not exist on connection '{}'",
+            missing, connection
+        )));
+    }
+
+    let mut selected = Vec::new();
+    let mut covered: Vec<&String> = Vec::new();
+    for job in jobs {
+        let exclude: Vec<String> = db_config
+            .exclude_databases
+            .iter()
+            .chain(&job.exclude_databases)
+            .cloned()
+            .collect();
+        let resolved = resolve_databases(&job.databases, &available, &exclude);
+        let filtered: Vec<String> = databases.iter().filter(|db| resolved.contains(db)).cloned().collect();
+        if filtered.is_empty() {
+            continue;
+        }
+        covered.extend(databases.iter().filter(|db| filtered.contains(db)));
+        selected.push(BackupJob {
+            databases: filtered,
+            ..job.clone()
+        });
+    }
+    if let Some(missing) = databases.iter().find(|db| !covered.contains(db)) {
+        return Err(BackupError::Config(format!(
+            "Database '{}' is not part of any enabled job for connection '{}'",
+            missing, connection
+        )));
+    }
+    Ok((db_config, selected))
+}
+
 async fn run_backup_now(config: &AppConfig, app_state: Arc<AppState>) {
-    println!("\n{}", style("Running all backup jobs...").yellow());
+    run_all_jobs(config, &app_state).await;
+
+    if !output::is_quiet() {
+        println!("\nPress Enter to continue...");
+        let _ = std::io::stdin().read_line(&mut String::new());
+    }
+}
+
+fn result_json(result: &BackupResult) -> serde_json::Value {
+    serde_json::json!({
+        "connection": result.connection_name,
+        "success": result.success,
+        "summary": result.summary_line(),
+        "databases": result.databases,
+        "file": result.file_path,
+        "size": result.file_size,
+        "duration_secs": result.duration_secs,
+        "error": result.error,
+        "skipped": result.skipped,
+        "db_errors": result
+            .db_errors
+            .iter()
+            .map(|(db, err)| serde_json::json!({ "database": db, "error": err }))
+            .collect::<Vec<_>>(),
+        "warnings": result
+            .db_warnings
+            .iter()
+            .map(|(db, w)| serde_json::json!({ "database": db, "warning": w }))
+            .collect::<Vec<_>>(),
+        "uploads": result.uploads,
+    })
+}
+
+async fn run_all_jobs(config: &AppConfig, app_state: &Arc<AppState>) -> bool {
+    let quiet = output::is_quiet();
+    if !quiet {
+        println!("\n{}", style("Running all backup jobs...").yellow());
+    }
 
     if config.backup_jobs.is_empty() {
-        println!(
-            "{}",
-            style("No backup jobs configured. Please configure databases first.").red()
-        );
-        return;
+        if quiet {
+            eprintln!("No backup jobs configured");
+        } else {
+            println!(
+                "{}",
+                style("No backup jobs configured. Please configure databases first.").red()
+            );
+        }
+        return false;
     }
 
-    let results = crate::backup::execute_all_jobs(config).await;
+    let results = crate::backup::execute_all_jobs(config, &app_state.jobs).await;
+    report_results(&results, app_state).await
+}
+
+/// Backs up one database right away through the normal pipeline, without needing
+/// a configured job for it.
+pub async fn run_single_database(app_state: Arc<AppState>, connection: &str, database: &str) -> Result<bool> {
+    let config = config::load()?;
+    let db_config = config
+        .databases
+        .iter()
+        .find(|d| d.name == connection)
+        .ok_or_else(|| BackupError::Config(format!("Unknown connection '{}'", connection)))?;
+    open_history_store(&config, &app_state).await;
+
+    let job = BackupJob {
+        db_config_name: db_config.name.clone(),
+        databases: vec![database.to_string()],
+        ..BackupJob::default()
+    };
+    if !output::is_quiet() {
+        println!("\n{}", style(format!("Backing up {}/{}...", connection, database)).yellow());
+    }
+    let queued = app_state.jobs.enqueue(&job.db_config_name, &job.databases);
+    let run = |token| crate::backup::execute_job_backup(&config, db_config, &job, Some(token));
+    let results = app_state.jobs.run(queued, run, |r: &Vec<BackupResult>| all_succeeded(r)).await.unwrap_or_default();
+    crate::database::close_shared_pools().await;
+    Ok(report_results(&results, &app_state).await)
+}
 
-    println!("\n{}", style("=== Backup Results ===").cyan().bold());
-    for result in &results {
+/// Records results in the history and prints them; returns whether all succeeded.
+async fn report_results(results: &[BackupResult], app_state: &Arc<AppState>) -> bool {
+    let quiet = output::is_quiet();
+    if !quiet {
+        println!("\n{}", style("=== Backup Results ===").cyan().bold());
+    }
+    for result in results {
         app_state.add_backup_entry(BackupEntry {
             timestamp: chrono::Utc::now(),
             connection_name: result.connection_name.clone(),
@@ -564,44 +1036,369 @@ async fn run_backup_now(config: &AppConfig, app_state: Arc<AppState>) {
             file_size: result.file_size.unwrap_or(0),
             duration_secs: result.duration_secs,
             error: result.error.clone(),
+            tags: result.tags.clone(),
+            uploads: result.uploads.clone(),
+            file_path: result.file_path.as_ref().map(|p| p.to_string_lossy().to_string()),
+            pinned: false,
         }).await;
-        
+        if let Some(pending) = &result.pending {
+            pending.clear();
+        }
+
+        if quiet {
+            println!("{}", result_json(result));
+            continue;
+        }
         if result.success {
-            println!(
-                "{} {} ({} databases) - {} ({:.2} MB, {} sec)",
-                style("✓").green(),
-                result.connection_name,
-                result.databases.len(),
-                style("Success").green(),
-                result.file_size.unwrap_or(0) as f64 / 1024.0 / 1024.0,
-                result.duration_secs
-            );
+            println!("{} {}", style("✓").green(), result.summary_line());
             println!("    Databases: {}", result.databases.join(", "));
+            if result.file_path.is_some() && result.uploads.is_empty() {
+                println!("    Uploads: none (local only)");
+            }
+            for upload in &result.uploads {
+                match &upload.error {
+                    None => println!("    {} Uploaded to {}", style("↑").green(), upload.target),
+                    Some(err) => println!(
+                        "    {} Upload to {} failed: {}",
+                        style("↑").red(),
+                        upload.target,
+                        err
+                    ),
+                }
+            }
         } else {
-            println!(
-                "{} {} - {} ({})",
-                style("✗").red(),
-                result.connection_name,
-                style("Failed").red(),
-                result.error.as_deref().unwrap_or("Unknown error")
-            );
+            println!("{} {}", style("✗").red(), result.summary_line());
+        }
+        if !result.skipped.is_empty() {
+            println!("    Skipped (unchanged): {}", result.skipped.join(", "));
         }
         for (db_name, err) in &result.db_errors {
             println!("    {} {}: {}", style("⚠").yellow(), db_name, err);
         }
+        for (db_name, warning) in &result.db_warnings {
+            println!("    {} {}: {}", style("!").yellow(), db_name, warning);
+        }
     }
 
     let success_count = results.iter().filter(|r| r.success).count();
-    println!(
-        "\nCompleted: {}/{} backup jobs successful",
-        style(success_count).green(),
-        results.len()
-    );
+    if !quiet {
+        println!(
+            "\nCompleted: {}/{} backup jobs successful",
+            style(success_count).green(),
+            results.len()
+        );
+    }
+    success_count == results.len()
+}
+
+async fn status_overview(config: &AppConfig, app_state: &Arc<AppState>, scheduler_running: bool) {
+    println!("\n{}", style("=== Status Overview ===").cyan().bold());
+    if config.databases.is_empty() {
+        println!("{}", style("No database connections configured.").red());
+    }
+
+    for db_config in &config.databases {
+        println!(
+            "\n{} ({}:{})",
+            style(&db_config.name).bold(),
+            db_config.host,
+            db_config.port
+        );
+
+        let check = match app_state.connection_check(&db_config.name) {
+            Some(check) if check.age_secs() < CONNECTION_CHECK_TTL_SECS => check,
+            _ => probe_connection(db_config, app_state).await,
+        };
+        match &check.error {
+            None => println!("  Connection: {}", describe_check(&check)),
+            Some(e) => println!("  Connection: {}: {}", describe_check(&check), e),
+        }
+
+        let last = app_state
+            .query_history(&HistoryQuery {
+                connection: Some(db_config.name.clone()),
+                limit: Some(1),
+                ..HistoryQuery::default()
+            })
+            .await
+            .into_iter()
+            .next();
+        match &last {
+            Some(entry) if entry.success => println!(
+                "  Last backup: {} {} ({:.2} MB)",
+                style("✓").green(),
+                config::format_local(&entry.timestamp, "%Y-%m-%d %H:%M:%S %Z"),
+                entry.file_size as f64 / 1024.0 / 1024.0
+            ),
+            Some(entry) => println!(
+                "  Last backup: {} {} ({})",
+                style("✗").red(),
+                config::format_local(&entry.timestamp, "%Y-%m-%d %H:%M:%S %Z"),
+                entry.error.as_deref().unwrap_or("Unknown error")
+            ),
+            None => println!("  Last backup: {}", style("never").dim()),
+        }
+
+        let jobs: Vec<_> = config
+            .backup_jobs
+            .iter()
+            .filter(|j| j.db_config_name == db_config.name)
+            .collect();
+        if jobs.is_empty() {
+            println!("  {}", style("No backup jobs").yellow());
+        }
+        for job in jobs {
+            let next_run = if !job.enabled {
+                style("-".to_string()).dim()
+            } else if !scheduler_running {
+                style("scheduler stopped".to_string()).dim()
+            } else {
+                match &last {
+                    Some(entry) => {
+                        let next = entry.timestamp
+                            + chrono::Duration::seconds(job.schedule.as_seconds() as i64);
+                        if next <= chrono::Utc::now() {
+                            style("due on next cycle".to_string()).yellow()
+                        } else {
+                            style(config::format_local(&next, "%Y-%m-%d %H:%M:%S %Z")).cyan()
+                        }
+                    }
+                    None => style("due on next cycle".to_string()).yellow(),
+                }
+            };
+            println!(
+                "  Job [{}] {} - {}, next run: {}",
+                if job.enabled {
+                    style("enabled").green()
+                } else {
+                    style("disabled").yellow()
+                },
+                job.databases.join(", "),
+                job.schedule,
+                next_run
+            );
+        }
+    }
+
+    println!("\nPress Enter to continue...");
+    let _ = std::io::stdin().read_line(&mut String::new());
+}
+
+async fn check_uploaders(config: &AppConfig) {
+    crate::upload::health::reset();
+    let uploaders = crate::upload::create_uploaders(&config.upload);
+    if uploaders.is_empty() {
+        return;
+    }
+    println!("\n{}", style("Checking uploaders...").yellow());
+    for (name, error) in crate::upload::health::check_uploaders(&config.upload, &uploaders).await {
+        match error {
+            None => println!("  {}: {}", name, style("OK").green()),
+            Some(e) => println!("  {}: {} ({})", name, style("FAILED").red(), e),
+        }
+    }
+}
+
+async fn retry_uploads(config: &AppConfig) {
+    if PendingUploads::load().is_empty() {
+        println!("{}", style("No pending uploads.").green());
+    } else {
+        println!("\n{}", style("Retrying pending uploads...").yellow());
+        let summary = retry_pending_uploads(&config.upload, crate::log::verbosity()).await;
+        println!(
+            "  Uploaded: {}, dropped (file missing): {}, still pending: {}",
+            style(summary.uploaded).green(),
+            summary.dropped,
+            style(summary.remaining).yellow()
+        );
+    }
 
     println!("\nPress Enter to continue...");
     let _ = std::io::stdin().read_line(&mut String::new());
 }
 
+/// Lists queued, running and recently finished backup jobs and offers to cancel
+/// an active one (e.g. a scheduler run in the background).
+async fn job_queue(app_state: &Arc<AppState>) {
+    let jobs = app_state.jobs.snapshot();
+    if jobs.is_empty() {
+        println!("{}", style("No backup jobs have been queued yet.").dim());
+    }
+    for job in &jobs {
+        let state = match job.state {
+            JobState::Pending => style("pending").yellow(),
+            JobState::Running => style("running").cyan(),
+            JobState::Done => style("done").green(),
+            JobState::Failed => style("failed").red(),
+            JobState::Cancelled => style("cancelled").dim(),
+        };
+        println!(
+            "  #{:<4} {:<10} {} [{}]",
+            job.id,
+            state,
+            job.connection_name,
+            job.databases.join(", ")
+        );
+    }
+
+    let active: Vec<_> = jobs.iter().filter(|j| j.state.is_active()).collect();
+    if !active.is_empty() {
+        let mut items: Vec<String> = active
+            .iter()
+            .map(|j| format!("Cancel #{} {} [{}]", j.id, j.connection_name, j.databases.join(", ")))
+            .collect();
+        items.push("Back".to_string());
+        let selection = Select::new()
+            .with_prompt("Cancel a job?")
+            .items(&items)
+            .default(items.len() - 1)
+            .interact_opt();
+        if let Ok(Some(idx)) = selection {
+            if let Some(job) = active.get(idx) {
+                if app_state.jobs.cancel(job.id) {
+                    app_state.add_log("INFO", &format!("Backup job {} cancelled", job.id)).await;
+                    println!("{}", style(format!("Cancelled job #{}", job.id)).green());
+                } else {
+                    println!("{}", style(format!("Job #{} already finished", job.id)).yellow());
+                }
+            }
+        }
+    }
+
+    println!("\nPress Enter to continue...");
+    let _ = std::io::stdin().read_line(&mut String::new());
+}
+
+fn archive_label(config: &AppConfig, archive: &std::path::Path) -> String {
+    let name = archive.strip_prefix(&config.local_backup_dir).unwrap_or(archive).display().to_string();
+    if retention::is_pinned(archive) {
+        format!("{} {}", name, style("[pinned]").yellow())
+    } else {
+        name
+    }
+}
+
+/// Pinned archives are never deleted by `max_total_bytes` pruning.
+fn pin_backups(config: &AppConfig) -> Result<()> {
+    let archives = restore::list_archives(config);
+    if archives.is_empty() {
+        println!("{}", style("No backup archives found.").red());
+        return Ok(());
+    }
+
+    let names: Vec<String> = archives
+        .iter()
+        .map(|p| p.strip_prefix(&config.local_backup_dir).unwrap_or(p).display().to_string())
+        .collect();
+    let pinned: Vec<bool> = archives.iter().map(|p| retention::is_pinned(p)).collect();
+    let selected = MultiSelect::new()
+        .with_prompt("Select archives to keep pinned (Space to toggle, Enter to confirm)")
+        .items(&names)
+        .defaults(&pinned)
+        .interact()
+        .map_err(|e| BackupError::Config(e.to_string()))?;
+
+    for (i, archive) in archives.iter().enumerate() {
+        let pin = selected.contains(&i);
+        if pin == pinned[i] {
+            continue;
+        }
+        retention::set_pinned(archive, pin)?;
+        let action = if pin { style("Pinned").yellow() } else { style("Unpinned").dim() };
+        println!("{} {}", action, names[i]);
+    }
+    Ok(())
+}
+
+async fn restore_backup(config: &AppConfig) -> Result<()> {
+    let archives = restore::list_archives(config);
+    if archives.is_empty() {
+        println!("{}", style("No backup archives found.").red());
+        return Ok(());
+    }
+
+    let archive_names: Vec<String> = archives.iter().map(|p| archive_label(config, p)).collect();
+    let selection = Select::new()
+        .with_prompt("Select archive to restore from")
+        .items(&archive_names)
+        .default(0)
+        .interact()
+        .map_err(|e| BackupError::Config(e.to_string()))?;
+    let archive_path = &archives[selection];
+    if let Some(metadata) = BackupMetadata::read_from_archive(archive_path) {
+        println!(
+            "{} {} from {} ({} databases{})",
+            style("Backup").dim(),
+            config::format_local(&metadata.timestamp, "%Y-%m-%d %H:%M:%S %Z"),
+            metadata.connection_name,
+            metadata.databases.len(),
+            if metadata.tags.is_empty() { String::new() } else { format!(", tags: {}", metadata.tags.join(", ")) }
+        );
+    }
+
+    let entries = restore::list_entries(archive_path)?;
+    if entries.is_empty() {
+        println!("{}", style("No SQL dumps found in this archive (NDJSON dumps can't be restored).").red());
+        return Ok(());
+    }
+
+    let labels: Vec<String> = entries.iter().map(|e| e.to_string()).collect();
+    let selected = MultiSelect::new()
+        .with_prompt("Select databases to restore (Space to select, Enter to confirm)")
+        .items(&labels)
+        .interact()
+        .map_err(|e| BackupError::Config(e.to_string()))?;
+    if selected.is_empty() {
+        println!("{}", style("No databases selected.").yellow());
+        return Ok(());
+    }
+
+    let connection_names: Vec<&str> = config.databases.iter().map(|d| d.name.as_str()).collect();
+    if connection_names.is_empty() {
+        println!("{}", style("No database connections configured.").red());
+        return Ok(());
+    }
+
+    let mut plan: Vec<(&ArchiveEntry, usize)> = Vec::new();
+    for &i in &selected {
+        let entry = &entries[i];
+        let default = restore::default_connection(archive_path, entry)
+            .and_then(|name| connection_names.iter().position(|n| *n == name))
+            .unwrap_or(0);
+        let conn_index = Select::new()
+            .with_prompt(format!("Restore {} into connection", entry))
+            .items(&connection_names)
+            .default(default)
+            .interact()
+            .map_err(|e| BackupError::Config(e.to_string()))?;
+        plan.push((entry, conn_index));
+    }
+
+    println!("\n{}", style("The following databases will be overwritten:").yellow());
+    for (entry, conn_index) in &plan {
+        println!("  {} -> {}/{}", entry, connection_names[*conn_index], entry.database);
+    }
+    let confirmed = Confirm::new()
+        .with_prompt("Continue?")
+        .default(false)
+        .interact()
+        .map_err(|e| BackupError::Config(e.to_string()))?;
+    if !confirmed {
+        return Ok(());
+    }
+
+    for (entry, conn_index) in plan {
+        let db_config = &config.databases[conn_index];
+        print!("  Restoring {}... ", entry);
+        match restore::restore_entry(db_config, archive_path, entry, &entry.database).await {
+            Ok(_) => println!("{}", style("OK").green()),
+            Err(e) => println!("{}: {}", style("FAILED").red(), e),
+        }
+    }
+
+    Ok(())
+}
+
 async fn edit_configuration(config: &mut AppConfig) -> Result<()> {
     loop {
         println!("\n{}", style("=== Edit Configuration ===").cyan().bold());
@@ -609,10 +1406,12 @@ async fn edit_configuration(config: &mut AppConfig) -> Result<()> {
         let edit_items = vec![
             EditOption::DatabaseConnection,
             EditOption::SelectDatabases,
+            EditOption::ToggleJobs,
             EditOption::ChangeSchedule,
             EditOption::UploadSettings,
             EditOption::WebDashboard,
             EditOption::BackupDirectory,
+            EditOption::Timezone,
             EditOption::Back,
         ];
 
@@ -626,46 +1425,64 @@ async fn edit_configuration(config: &mut AppConfig) -> Result<()> {
             Ok(None) | Err(_) => break,
         };
 
-        match edit_items[selection] {
-            EditOption::DatabaseConnection => {
-                super::wizard::configure_database(config).await?;
-            }
-            EditOption::SelectDatabases => {
-                super::wizard::select_databases(config).await?;
-            }
+        if edit_items[selection] == EditOption::Back {
+            break;
+        }
+
+        // Each step edits a copy that only replaces the config once the step completes,
+        // so a cancelled or failed prompt leaves the previous settings untouched.
+        let mut draft = config.clone();
+        let result = match edit_items[selection] {
+            EditOption::DatabaseConnection => super::wizard::configure_database(&mut draft).await,
+            EditOption::SelectDatabases => super::wizard::select_databases(&mut draft).await,
+            EditOption::ToggleJobs => super::wizard::toggle_jobs(&mut draft),
             EditOption::ChangeSchedule => {
-                if config.backup_jobs.is_empty() {
+                if draft.backup_jobs.is_empty() {
                     println!(
                         "{}",
                         style("No backup jobs configured yet. Please select databases first.").red()
                     );
+                    Ok(())
                 } else {
-                    let schedule = super::wizard::configure_schedule()?;
-                    for job in &mut config.backup_jobs {
-                        job.schedule = schedule.clone();
-                    }
-                    println!("{}", style("Schedule updated for all jobs.").green());
+                    super::wizard::configure_schedule().map(|schedule| {
+                        for job in &mut draft.backup_jobs {
+                            job.schedule = schedule.clone();
+                        }
+                        println!("{}", style("Schedule updated for all jobs.").green());
+                    })
                 }
             }
-            EditOption::UploadSettings => {
-                super::wizard::configure_discord(config).await?;
-            }
-            EditOption::WebDashboard => {
-                super::wizard::configure_web_dashboard(config)?;
-            }
-            EditOption::BackupDirectory => {
-                super::wizard::configure_backup_directory(config)?;
-            }
-            EditOption::Back => {
-                break;
-            }
+            EditOption::UploadSettings => super::wizard::configure_discord(&mut draft).await,
+            EditOption::WebDashboard => super::wizard::configure_web_dashboard(&mut draft),
+            EditOption::BackupDirectory => super::wizard::configure_backup_directory(&mut draft),
+            EditOption::Timezone => super::wizard::configure_timezone(&mut draft),
+            EditOption::Back => Ok(()),
+        };
+
+        match result {
+            Ok(()) => *config = draft,
+            Err(e) => println!("{}: {} (no changes made)", style("Cancelled").yellow(), e),
         }
     }
 
     Ok(())
 }
 
-async fn test_database_connection(config: &AppConfig) {
+fn open_backup_directory(config: &AppConfig) {
+    let path = &config.local_backup_dir;
+    println!("\nBackup directory: {}", style(path.display()).cyan());
+
+    if !path.exists() {
+        println!("{}", style("Directory does not exist yet (no backups have run).").yellow());
+    } else if let Err(e) = open::that(path) {
+        println!("{}: {}", style("Could not open file manager").yellow(), e);
+    }
+
+    println!("\nPress Enter to continue...");
+    let _ = std::io::stdin().read_line(&mut String::new());
+}
+
+async fn test_database_connection(config: &AppConfig, app_state: &AppState) {
     if config.databases.is_empty() {
         println!(
             "{}",
@@ -678,12 +1495,9 @@ async fn test_database_connection(config: &AppConfig) {
 
     for db_config in &config.databases {
         print!("  {} ({})... ", db_config.name, db_config.engine);
-        match create_driver(db_config) {
-            Ok(driver) => match driver.test_connection().await {
-                Ok(_) => println!("{}", style("OK").green()),
-                Err(e) => println!("{}: {}", style("FAILED").red(), e),
-            },
-            Err(e) => println!("{}: {}", style("ERROR").red(), e),
+        match probe_connection(db_config, app_state).await.error {
+            None => println!("{}", style("OK").green()),
+            Some(e) => println!("{}: {}", style("FAILED").red(), e),
         }
     }
 
@@ -695,9 +1509,14 @@ async fn test_discord_upload(config: &AppConfig) {
     match &config.upload.discord {
         Some(discord_config) => {
             println!("\n{}", style("Testing Discord connection...").yellow());
-            let uploader = DiscordUploader::new(discord_config);
-            match uploader.test_connection().await {
-                Ok(_) => println!("{}", style("Discord connection successful!").green()),
+            if let Some(proxy) = proxy_summary(&config.upload.http) {
+                println!("  Using proxy: {}", style(proxy).cyan());
+            }
+            match DiscordUploader::new(discord_config, &config.upload.http) {
+                Ok(uploader) => match uploader.test_connection().await {
+                    Ok(_) => println!("{}", style("Discord connection successful!").green()),
+                    Err(e) => println!("{}: {}", style("Discord test failed").red(), e),
+                },
                 Err(e) => println!("{}: {}", style("Discord test failed").red(), e),
             }
         }
@@ -1,4 +1,14 @@
+pub mod args;
+pub mod benchmark;
+pub mod connections;
+pub mod dump;
 pub mod menu;
+pub mod output;
+pub mod recovery;
+pub mod serve;
+pub mod test_restore;
+pub mod update;
 pub mod wizard;
 
-pub use menu::run_menu;
+pub use args::{Args, Command, ConfigCommand};
+pub use menu::{run_menu, run_once, run_single_database};
@@ -0,0 +1,132 @@
+use crate::config::{self, AppConfig, ConfigFormat};
+use crate::error::{BackupError, Result};
+use chrono::Utc;
+use console::style;
+use dialoguer::Select;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Lines shown on each side of the line a parse error points at.
+const CONTEXT_LINES: usize = 2;
+
+/// Offers a way out when the config file doesn't parse: a copy of the broken file is
+/// kept, then the user can fix it in an editor or start over with the setup wizard.
+/// Returns the loaded config, or a default one when the wizard should run.
+pub fn recover_malformed_config(mut error: String) -> Result<AppConfig> {
+    let path = config::config_path();
+    let backup = backup_broken_file(&path)?;
+    println!(
+        "\n{} {} could not be parsed. A copy was saved to {}",
+        style("✗").red(),
+        path.display(),
+        backup.display()
+    );
+
+    loop {
+        println!("\n{}", style(&error).red());
+        if ConfigFormat::from_path(&path) == ConfigFormat::Json {
+            // TOML errors already quote the offending line; serde_json only gives its number.
+            if let Some(context) = fs::read_to_string(&path).ok().and_then(|c| line_context(&c, &error)) {
+                println!("{}", context);
+            }
+        }
+
+        let options = vec![
+            "Open the file in an editor and try again",
+            "Start over with the setup wizard",
+            "Exit",
+        ];
+        let choice = Select::new()
+            .with_prompt("How do you want to continue?")
+            .items(&options)
+            .default(0)
+            .interact()
+            .map_err(|e| BackupError::Config(e.to_string()))?;
+
+        match choice {
+            0 => {
+                open_in_editor(&path)?;
+                match config::load() {
+                    Ok(config) => return Ok(config),
+                    Err(BackupError::Serialization(e)) => error = e,
+                    Err(e) => return Err(e),
+                }
+            }
+            1 => {
+                fs::remove_file(&path)?;
+                return Ok(AppConfig::default());
+            }
+            _ => return Err(BackupError::Serialization(error)),
+        }
+    }
+}
+
+fn backup_broken_file(path: &Path) -> Result<PathBuf> {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".broken-{}", Utc::now().format("%Y%m%d_%H%M%S")));
+    let backup = path.with_file_name(name);
+    fs::copy(path, &backup)?;
+    Ok(backup)
+}
+
+fn open_in_editor(path: &Path) -> Result<()> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| if cfg!(windows) { "notepad" } else { "vi" }.to_string());
+    let mut parts = editor.split_whitespace();
+    let program = parts
+        .next()
+        .ok_or_else(|| BackupError::Config("EDITOR is empty".to_string()))?;
+
+    let status = Command::new(program)
+        .args(parts)
+        .arg(path)
+        .status()
+        .map_err(|e| BackupError::Config(format!("Failed to start editor '{}': {}", editor, e)))?;
+    if !status.success() {
+        return Err(BackupError::Config(format!("Editor '{}' exited with {}", editor, status)));
+    }
+    Ok(())
+}
+
+/// The lines around the `line N` an error message refers to, with that line marked.
+fn line_context(contents: &str, error: &str) -> Option<String> {
+    let rest = &error[error.find("line ")? + "line ".len()..];
+    let digits: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+    let line: usize = digits.parse().ok().filter(|&n| n > 0)?;
+
+    let lines: Vec<&str> = contents.lines().collect();
+    if line > lines.len() {
+        return None;
+    }
+    let first = line.saturating_sub(CONTEXT_LINES).max(1);
+    let last = (line + CONTEXT_LINES).min(lines.len());
+    Some(
+        (first..=last)
+            .map(|n| {
+                let marker = if n == line { ">" } else { " " };
+                format!("{} {:>4} | {}", marker, n, lines[n - 1])
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_context() {
+        let contents = "{\n  \"a\": 1,\n  \"b\": ,\n  \"c\": 3\n}";
+        let context = line_context(contents, "expected value at line 3 column 8").unwrap();
+        assert_eq!(
+            context,
+            "     1 | {\n     2 |   \"a\": 1,\n>    3 |   \"b\": ,\n     4 |   \"c\": 3\n     5 | }"
+        );
+
+        assert_eq!(line_context(contents, "EOF while parsing"), None);
+        assert_eq!(line_context(contents, "error at line 9 column 1"), None);
+    }
+}
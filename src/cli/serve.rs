@@ -0,0 +1,62 @@
+use crate::backup::schedule_state::ScheduleState;
+use crate::backup::{run_scheduler, run_scheduler_once, watchdog};
+use crate::config;
+use crate::error::Result;
+use crate::web::{self, AppState};
+use std::sync::atomic::AtomicUsize;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+/// Runs the scheduler, and the web dashboard when it is enabled, in the foreground until
+/// Ctrl+C or SIGTERM. Under systemd, readiness is reported once both are up.
+pub async fn run_service(shutdown: Arc<AtomicUsize>, app_state: Arc<AppState>) -> Result<()> {
+    let config = config::load()?;
+    super::menu::update_config_summary(&config, &app_state).await;
+    super::menu::open_history_store(&config, &app_state).await;
+
+    if !config.web.enabled {
+        info!("Web dashboard is disabled, running the scheduler only");
+    } else if config.web.username.is_empty() || config.web.password.is_empty() {
+        warn!("Web dashboard credentials not set, running the scheduler only");
+    } else {
+        app_state.set_credentials(config.web.username.clone(), config.web.password.clone()).await;
+        app_state.set_api_token(config.web.api_token.clone()).await;
+        // Bind before reporting ready, so a taken port fails the service start.
+        let listener = web::bind_server(&config.web).await?;
+        tokio::spawn(web::serve(app_state.clone(), config.web.clone(), listener));
+    }
+
+    let scheduler = tokio::spawn(run_scheduler(Arc::new(config), shutdown, app_state));
+    watchdog::notify_ready();
+    if let Err(e) = scheduler.await {
+        warn!("Scheduler task ended abnormally: {}", e);
+    }
+    Ok(())
+}
+
+/// One scheduler cycle without the dashboard, so cron can decide when it runs. Due jobs
+/// are judged by the last-run times the scheduler keeps on disk; the cycle's log is
+/// printed at the end. Returns false if any backup failed; a cycle that finds another
+/// one still running skips and counts as a success.
+pub async fn run_once(shutdown: Arc<AtomicUsize>, app_state: Arc<AppState>) -> Result<bool> {
+    let config = config::load()?;
+    let Some(_cycle_lock) = ScheduleState::try_lock_cycle(&ScheduleState::cycle_lock_path())? else {
+        eprintln!("Another scheduler cycle is already running; skipping this one");
+        return Ok(true);
+    };
+    super::menu::update_config_summary(&config, &app_state).await;
+    super::menu::open_history_store(&config, &app_state).await;
+
+    let success = run_scheduler_once(&config, shutdown, app_state.clone()).await;
+    if !super::output::is_quiet() {
+        for log in app_state.scheduler_logs.read().await.iter().rev() {
+            println!(
+                "{} [{}] {}",
+                config::format_local(&log.timestamp, "%Y-%m-%d %H:%M:%S"),
+                log.level,
+                log.message
+            );
+        }
+    }
+    Ok(success)
+}
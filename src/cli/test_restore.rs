@@ -0,0 +1,77 @@
+use super::output;
+use crate::backup::restore;
+use crate::config;
+use crate::error::{BackupError, Result};
+use console::style;
+use std::path::PathBuf;
+
+/// Restores an archive into the scratch connection and reports each entry.
+/// Returns whether every entry restored without errors; row count differences
+/// are reported but don't fail the check, since the source may have changed.
+pub async fn run_test_restore(archive: Option<PathBuf>, compare_rows: bool) -> Result<bool> {
+    let config = config::load()?;
+    let archive_path = match archive {
+        Some(path) => path,
+        None => restore::list_archives(&config)
+            .into_iter()
+            .next()
+            .ok_or_else(|| BackupError::Config("No backup archives found".to_string()))?,
+    };
+    let quiet = output::is_quiet();
+
+    if !quiet {
+        println!(
+            "{}",
+            style(format!("Test-restoring {}...", archive_path.display())).yellow()
+        );
+    }
+    let reports = restore::test_restore(&config, &archive_path, compare_rows).await?;
+
+    for report in &reports {
+        if quiet {
+            println!(
+                "{}",
+                serde_json::json!({
+                    "archive": archive_path,
+                    "entry": report.entry.to_string(),
+                    "success": report.is_ok(),
+                    "error": report.error,
+                    "row_count_mismatches": report
+                        .row_count_mismatches
+                        .iter()
+                        .map(|(table, restored, source)| serde_json::json!({
+                            "table": table,
+                            "restored": restored,
+                            "source": source,
+                        }))
+                        .collect::<Vec<_>>(),
+                })
+            );
+            continue;
+        }
+
+        match &report.error {
+            None => println!("{} {}", style("✓").green(), report.entry),
+            Some(err) => println!("{} {}: {}", style("✗").red(), report.entry, err),
+        }
+        for (table, restored, source) in &report.row_count_mismatches {
+            println!(
+                "    {} {}: {} rows restored, {} in source",
+                style("!").yellow(),
+                table,
+                restored,
+                source
+            );
+        }
+    }
+
+    let ok = reports.iter().all(|r| r.is_ok());
+    if !quiet {
+        println!(
+            "\nRestored {}/{} entries",
+            reports.iter().filter(|r| r.is_ok()).count(),
+            reports.len()
+        );
+    }
+    Ok(ok)
+}
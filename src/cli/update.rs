@@ -0,0 +1,87 @@
+use crate::config::{self, HttpConfig};
+use crate::upload::build_client;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use tracing::debug;
+
+const RELEASES_URL: &str = "https://api.github.com/repos/quatrecentdouze/tlm-sql-backup/releases/latest";
+const CACHE_HOURS: i64 = 24;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct UpdateCache {
+    checked_at: DateTime<Utc>,
+    latest_version: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Release {
+    tag_name: String,
+}
+
+fn parse_version(version: &str) -> Vec<u64> {
+    version
+        .trim_start_matches('v')
+        .split(['.', '-'])
+        .map_while(|part| part.parse().ok())
+        .collect()
+}
+
+fn is_newer(latest: &str, current: &str) -> bool {
+    parse_version(latest) > parse_version(current)
+}
+
+async fn fetch_latest_version(http: &HttpConfig) -> Option<String> {
+    let client = build_client(http).ok()?;
+    let response = client
+        .get(RELEASES_URL)
+        .header("Accept", "application/vnd.github+json")
+        .timeout(std::time::Duration::from_secs(5))
+        .send()
+        .await
+        .ok()?;
+    if !response.status().is_success() {
+        debug!("Update check returned {}", response.status());
+        return None;
+    }
+    let release: Release = response.json().await.ok()?;
+    Some(release.tag_name.trim_start_matches('v').to_string())
+}
+
+pub async fn check_for_update(http: &HttpConfig) -> Option<String> {
+    let cache_path = config::config_dir().join("update_check.json");
+    let cached = fs::read_to_string(&cache_path)
+        .ok()
+        .and_then(|c| serde_json::from_str::<UpdateCache>(&c).ok())
+        .filter(|c| Utc::now() - c.checked_at < Duration::hours(CACHE_HOURS));
+
+    let latest = match cached {
+        Some(cache) => cache.latest_version,
+        None => {
+            let latest = fetch_latest_version(http).await?;
+            let cache = UpdateCache {
+                checked_at: Utc::now(),
+                latest_version: latest.clone(),
+            };
+            if let Ok(contents) = serde_json::to_string(&cache) {
+                let _ = fs::write(&cache_path, contents);
+            }
+            latest
+        }
+    };
+
+    is_newer(&latest, env!("CARGO_PKG_VERSION")).then_some(latest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_newer() {
+        assert!(is_newer("v0.2.0", "0.1.9"));
+        assert!(is_newer("1.0.0", "0.9.12"));
+        assert!(!is_newer("0.1.0", "0.1.0"));
+        assert!(!is_newer("v0.1.0-beta", "0.1.0"));
+    }
+}
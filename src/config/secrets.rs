@@ -0,0 +1,71 @@
+use super::{expand_home, AppConfig};
+use crate::error::{BackupError, Result};
+use std::fs;
+use std::path::Path;
+
+pub const FILE_SECRET_PREFIX: &str = "file:";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SecretRef {
+    pub reference: String,
+    pub resolved: String,
+}
+
+pub fn read_secret_file(value: &str) -> Result<Option<String>> {
+    let Some(path) = value.strip_prefix(FILE_SECRET_PREFIX) else {
+        return Ok(None);
+    };
+    let path = expand_home(Path::new(path));
+    let contents = fs::read_to_string(&path).map_err(|e| {
+        BackupError::Config(format!("Failed to read secret file {:?}: {}", path, e))
+    })?;
+    Ok(Some(contents.trim().to_string()))
+}
+
+fn secret_fields(config: &mut AppConfig) -> Vec<(String, &mut String)> {
+    let mut fields = Vec::new();
+    for db in &mut config.databases {
+        fields.push((format!("databases.{}.password", db.name), &mut db.password));
+    }
+    if let Some(discord) = &mut config.upload.discord {
+        fields.push(("upload.discord.bot_token".to_string(), &mut discord.bot_token));
+    }
+    fields.push(("web.password".to_string(), &mut config.web.password));
+    if let Some(token) = &mut config.web.api_token {
+        fields.push(("web.api_token".to_string(), token));
+    }
+    fields
+}
+
+pub fn resolve_secrets(config: &mut AppConfig) -> Result<()> {
+    let mut refs = std::collections::HashMap::new();
+    for (key, value) in secret_fields(config) {
+        if let Some(secret) = read_secret_file(value)? {
+            refs.insert(
+                key,
+                SecretRef {
+                    reference: value.clone(),
+                    resolved: secret.clone(),
+                },
+            );
+            *value = secret;
+        }
+    }
+    config.secret_refs = refs;
+    Ok(())
+}
+
+// Writes `file:` references back in place of the secrets they resolved to, unless
+// the value was changed since loading.
+pub fn restore_secret_refs(config: &AppConfig) -> AppConfig {
+    let mut config = config.clone();
+    let refs = std::mem::take(&mut config.secret_refs);
+    for (key, value) in secret_fields(&mut config) {
+        if let Some(secret_ref) = refs.get(&key) {
+            if *value == secret_ref.resolved {
+                *value = secret_ref.reference.clone();
+            }
+        }
+    }
+    config
+}
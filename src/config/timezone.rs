@@ -0,0 +1,49 @@
+use crate::error::{BackupError, Result};
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use std::sync::RwLock;
+
+static DISPLAY_TIMEZONE: RwLock<Option<Tz>> = RwLock::new(None);
+
+pub fn parse_timezone(name: &str) -> Result<Tz> {
+    name.trim()
+        .parse::<Tz>()
+        .map_err(|_| BackupError::Config(format!("Unknown timezone '{}'", name)))
+}
+
+pub fn set_display_timezone(name: Option<&str>) -> Result<()> {
+    let tz = name.map(parse_timezone).transpose()?;
+    *DISPLAY_TIMEZONE.write().unwrap_or_else(|e| e.into_inner()) = tz;
+    Ok(())
+}
+
+pub fn display_timezone_name() -> String {
+    DISPLAY_TIMEZONE
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .map(|tz| tz.name().to_string())
+        .unwrap_or_else(|| "UTC".to_string())
+}
+
+pub fn format_local(timestamp: &DateTime<Utc>, fmt: &str) -> String {
+    match *DISPLAY_TIMEZONE.read().unwrap_or_else(|e| e.into_inner()) {
+        Some(tz) => timestamp.with_timezone(&tz).format(fmt).to_string(),
+        None => timestamp.format(fmt).to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_parse_timezone() {
+        assert_eq!(parse_timezone("Europe/Paris").unwrap(), chrono_tz::Europe::Paris);
+        assert!(parse_timezone("Mars/Olympus_Mons").is_err());
+
+        let ts = Utc.with_ymd_and_hms(2024, 1, 15, 2, 0, 0).unwrap();
+        let paris = ts.with_timezone(&parse_timezone("Europe/Paris").unwrap());
+        assert_eq!(paris.format("%H:%M %Z").to_string(), "03:00 CET");
+    }
+}
@@ -1,11 +1,77 @@
+use crate::config::{DumpFormat, PkRange};
 use crate::error::Result;
+use crate::log::Verbosity;
 use async_trait::async_trait;
-use std::io::Write;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::{BufRead, Write};use std::sync::Arc;
+#[derive(Debug, Clone)]
+pub struct DumpProgress {
+    pub database: String,
+    pub table: String,
+    pub table_index: usize,
+    pub table_count: usize,
+    /// Position of this database among the ones dumped by the same job, from 1.
+    pub database_index: usize,
+    pub database_count: usize,
+}
+
+/// Binary log coordinates captured at the start of a dump's consistent snapshot.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BinlogPosition {
+    pub file: String,
+    pub position: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gtid_executed: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DumpReport {
+    pub warnings: Vec<String>,
+    pub binlog_position: Option<BinlogPosition>,
+}
+
+/// Tables whose output is already in a partial dump file, so a retried dump can
+/// append the remaining ones instead of starting over.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DumpCheckpoint {
+    /// Completed tables, in dump order.
+    pub tables: Vec<String>,
+    /// Bytes written to the dump once the last completed table was flushed.
+    pub offset: u64,
+}
+
+pub type ProgressCallback = Arc<dyn Fn(DumpProgress) + Send + Sync>;
+pub type CheckpointCallback = Arc<dyn Fn(&DumpCheckpoint) + Send + Sync>;
 #[async_trait]
-pub trait DatabaseDriver: Send + Sync {
-    async fn test_connection(&self) -> Result<()>;
-    async fn list_databases(&self) -> Result<Vec<String>>;
-    async fn dump_database(&self, db_name: &str, writer: Box<dyn Write + Send>) -> Result<()>;
-    async fn dump_database_silent(&self, db_name: &str, writer: Box<dyn Write + Send>, silent: bool) -> Result<()>;
+pub trait DatabaseDriver: Send + Sync {    async fn test_connection(&self) -> Result<()>;    async fn list_databases(&self) -> Result<Vec<String>>;    async fn dump_database(&self, db_name: &str, writer: Box<dyn Write + Send>) -> Result<DumpReport>;    async fn dump_database_silent(
+        &self,        db_name: &str,
+        writer: Box<dyn Write + Send>,
+        verbosity: Verbosity,
+        progress: Option<ProgressCallback>,
+    ) -> Result<DumpReport>;
+    /// Like `dump_database_silent`, but continues after the tables in `resume`, whose
+    /// output `writer` already holds, and reports a checkpoint after every table.
+    async fn dump_database_resumable(
+        &self,
+        db_name: &str,
+        writer: Box<dyn Write + Send>,
+        verbosity: Verbosity,
+        progress: Option<ProgressCallback>,
+        resume: Option<DumpCheckpoint>,
+        on_checkpoint: Option<CheckpointCallback>,
+    ) -> Result<DumpReport>;
+    async fn change_marker(&self, db_name: &str) -> Result<Option<String>>;
+    async fn restore_database(&self, db_name: &str, reader: Box<dyn BufRead + Send>) -> Result<()>;
+    async fn drop_database(&self, db_name: &str) -> Result<()>;
+    /// Exact row count of every table, keyed by table name.
+    async fn row_counts(&self, db_name: &str) -> Result<BTreeMap<String, u64>>;
+    /// Disconnects the driver's connections instead of leaving them to be reaped
+    /// when the driver is dropped. The driver must not be used afterwards.
+    async fn close(&self) -> Result<()>;
+    /// Limits the rows dumped from matching tables; set before dumping.
+    fn set_pk_ranges(&mut self, ranges: Vec<PkRange>);
+    /// Output format of the dumps; set before dumping.
+    fn set_dump_format(&mut self, format: DumpFormat);
     fn engine_name(&self) -> &'static str;
 }
@@ -0,0 +1,171 @@
+//! Dump/restore round trips against a throwaway MySQL server. Needs Docker; run with
+//! `cargo test --features integration`.
+
+use super::create_driver;
+use crate::config::DatabaseConfig;
+use mysql_async::prelude::*;
+use mysql_async::{Pool, Value};
+use std::fs::File;
+use std::io::{BufReader, Write};
+use std::time::Duration;
+use tempfile::tempdir;
+use testcontainers_modules::mysql::Mysql;
+use testcontainers_modules::testcontainers::runners::AsyncRunner;
+use testcontainers_modules::testcontainers::ContainerAsync;
+
+const SCHEMA: &str = "CREATE TABLE `samples` (
+    `id` INT NOT NULL PRIMARY KEY,
+    `label` VARCHAR(64) NOT NULL,
+    `note` TEXT NULL,
+    `amount` DECIMAL(12, 4) NULL,
+    `ratio` DOUBLE NULL,
+    `day` DATE NULL,
+    `moment` DATETIME(6) NULL,
+    `elapsed` TIME(6) NULL,
+    `payload` BLOB NULL,
+    `doc` JSON NULL
+)";
+
+const ROWS: &str = r#"INSERT INTO `samples` VALUES
+    (1, 'plain', NULL, 12.5000, 0.25, '2024-02-29', '2024-02-29 23:59:59.123456', '-838:59:59.000000', X'00FF0A0D275C', '{"a": [1, 2], "b": "x\\"y"}'),
+    (2, 'it''s a \\ "quote"', 'line one\nline two\r\n', -0.0001, -1e300, '1000-01-01', '1970-01-01 00:00:01.000000', '00:00:00.500000', X'', 'null'),
+    (3, '', '', NULL, NULL, NULL, NULL, NULL, NULL, NULL)"#;
+
+async fn start_server() -> (ContainerAsync<Mysql>, DatabaseConfig) {
+    let container = Mysql::default().start().await.expect("failed to start MySQL container");
+    let config = DatabaseConfig {
+        name: "integration".to_string(),
+        host: container.get_host().await.unwrap().to_string(),
+        port: container.get_host_port_ipv4(3306).await.unwrap(),
+        username: "root".to_string(),
+        ..DatabaseConfig::default()
+    };
+    (container, config)
+}
+
+fn pool(config: &DatabaseConfig) -> Pool {
+    Pool::new(format!("mysql://{}@{}:{}", config.username, config.host, config.port).as_str())
+}
+
+async fn table_contents(pool: &Pool, database: &str) -> Vec<Vec<Value>> {
+    let mut conn = pool.get_conn().await.unwrap();
+    conn.query_map(
+        format!("SELECT * FROM `{}`.`samples` ORDER BY `id`", database),
+        |row: mysql_async::Row| row.unwrap(),
+    )
+    .await
+    .unwrap()
+}
+
+/// Dumps `source`, restores it as `target` and returns both tables' rows.
+async fn round_trip(config: &DatabaseConfig, source: &str, target: &str) -> (Vec<Vec<Value>>, Vec<Vec<Value>>) {
+    let pool = pool(config);
+    let mut conn = pool.get_conn().await.unwrap();
+    conn.query_drop(format!("CREATE DATABASE `{}`", source)).await.unwrap();
+    conn.query_drop(format!("USE `{}`", source)).await.unwrap();
+    conn.query_drop(SCHEMA).await.unwrap();
+    conn.query_drop(ROWS).await.unwrap();
+    drop(conn);
+
+    let dir = tempdir().unwrap();
+    let dump_path = dir.path().join("dump.sql");
+    let driver = create_driver(config).unwrap();
+    driver
+        .dump_database(source, Box::new(File::create(&dump_path).unwrap()))
+        .await
+        .unwrap();
+    driver
+        .restore_database(target, Box::new(BufReader::new(File::open(&dump_path).unwrap())))
+        .await
+        .unwrap();
+    driver.close().await.unwrap();
+
+    let contents = (table_contents(&pool, source).await, table_contents(&pool, target).await);
+    pool.disconnect().await.unwrap();
+    contents
+}
+
+#[tokio::test]
+async fn test_dump_restore_round_trip() {
+    let (_container, config) = start_server().await;
+    let (original, restored) = round_trip(&config, "source", "copy").await;
+    assert_eq!(original.len(), 3);
+    assert_eq!(restored, original);
+}
+
+#[tokio::test]
+async fn test_dump_restore_round_trip_with_options() {
+    let (_container, config) = start_server().await;
+    let config = DatabaseConfig {
+        extended_inserts: false,
+        hex_blob: true,
+        set_time_zone: Some("+00:00".to_string()),
+        ..config
+    };
+    let (original, restored) = round_trip(&config, "source", "copy").await;
+    assert_eq!(restored, original);
+}
+
+/// Writer that stalls once after `stall_after` bytes, long enough for the server's
+/// timeouts to expire while it is blocked sending the rest of the result set.
+struct StallingWriter {
+    inner: File,
+    written: usize,
+    stall_after: usize,
+    stall: Option<Duration>,
+}
+
+impl Write for StallingWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.written >= self.stall_after {
+            if let Some(stall) = self.stall.take() {
+                std::thread::sleep(stall);
+            }
+        }
+        let n = self.inner.write(buf)?;
+        self.written += n;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[tokio::test]
+async fn test_dump_outlasts_low_server_timeouts() {
+    let (_container, config) = start_server().await;
+    let pool = pool(&config);
+    let mut conn = pool.get_conn().await.unwrap();
+    conn.query_drop("SET GLOBAL wait_timeout = 2, GLOBAL net_write_timeout = 1").await.unwrap();
+    conn.query_drop("CREATE DATABASE `slow`").await.unwrap();
+    conn.query_drop("CREATE TABLE `slow`.`big` (`id` INT NOT NULL PRIMARY KEY, `payload` VARCHAR(1000) NOT NULL)")
+        .await
+        .unwrap();
+    conn.query_drop("SET SESSION cte_max_recursion_depth = 20000").await.unwrap();
+    conn.query_drop(
+        "INSERT INTO `slow`.`big` WITH RECURSIVE seq(n) AS \
+         (SELECT 1 UNION ALL SELECT n + 1 FROM seq WHERE n < 20000) \
+         SELECT n, REPEAT('x', 1000) FROM seq",
+    )
+    .await
+    .unwrap();
+    drop(conn);
+    pool.disconnect().await.unwrap();
+
+    // The 20 MB result set doesn't fit in the socket buffers, so the server is still
+    // sending rows while the writer stalls past both GLOBAL timeouts.
+    let dir = tempdir().unwrap();
+    let dump_path = dir.path().join("slow.sql");
+    let writer = StallingWriter {
+        inner: File::create(&dump_path).unwrap(),
+        written: 0,
+        stall_after: 1024 * 1024,
+        stall: Some(Duration::from_secs(4)),
+    };
+    let driver = create_driver(&config).unwrap();
+    driver.dump_database("slow", Box::new(writer)).await.unwrap();
+    driver.close().await.unwrap();
+
+    assert!(std::fs::metadata(&dump_path).unwrap().len() > 20_000 * 1000);
+}
@@ -1,8 +1,14 @@
 mod driver;
+#[cfg(all(test, feature = "integration"))]
+mod integration_tests;
 mod mysql;
+pub mod option_file;
+pub mod pattern;
 
-pub use driver::DatabaseDriver;
-pub use mysql::MysqlDriver;
+pub use driver::{
+    BinlogPosition, CheckpointCallback, DatabaseDriver, DumpCheckpoint, DumpProgress, DumpReport, ProgressCallback,
+};
+pub use mysql::{close_shared_pools, MysqlDriver};
 
 use crate::config::{DatabaseConfig, DatabaseEngine};
 use crate::error::Result;
@@ -14,3 +20,13 @@ pub fn create_driver(config: &DatabaseConfig) -> Result<Box<dyn DatabaseDriver>>
         }
     }
 }
+
+/// Like `create_driver`, but reusing the connections of earlier jobs on the same connection.
+pub fn create_shared_driver(config: &DatabaseConfig) -> Result<Box<dyn DatabaseDriver>> {
+    match config.engine {
+        DatabaseEngine::MySQL => {
+            let driver = MysqlDriver::shared(config)?;
+            Ok(Box::new(driver))
+        }
+    }
+}
@@ -1,34 +1,198 @@
-use super::driver::DatabaseDriver;
-use crate::config::DatabaseConfig;
+use super::driver::{
+    BinlogPosition, CheckpointCallback, DatabaseDriver, DumpCheckpoint, DumpProgress, DumpReport, ProgressCallback,
+};
+use super::option_file::resolve_credentials;
+use crate::config::{DatabaseConfig, DumpFormat, EmptyDatabasePolicy, InsertMode, LargeValuePolicy, NullFallback, PkRange, TableLock, UnsupportedTypePolicy};
 use crate::error::{BackupError, Result};
+use crate::log::Verbosity;
 use async_trait::async_trait;
 use mysql_async::prelude::*;
-use mysql_async::{Conn, Opts, OptsBuilder, Pool, Row};
-use std::io::Write;
-use tracing::{debug, info};
+use mysql_async::{Conn, Opts, OptsBuilder, Pool, PoolConstraints, PoolOpts, Row};
+use std::collections::BTreeMap;
+use std::io::{BufRead, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+const HEX_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Rows written between throttle checks in NDJSON dumps, like a batch of INSERT rows.
+const NDJSON_THROTTLE_ROWS: u64 = 100;
+
+/// Idle connections in shared pools are closed after this long, so they don't sit
+/// until the next scheduler cycle and run into the server's wait_timeout.
+const SHARED_POOL_IDLE_SECS: u64 = 300;
+
+/// Pools reused by the backup jobs of each connection, keyed by connection name. An
+/// entry is only reused while its options match the connection's current settings.
+static SHARED_POOLS: Mutex<BTreeMap<String, SharedPools>> = Mutex::new(BTreeMap::new());
+static NEXT_POOL_GENERATION: AtomicU64 = AtomicU64::new(1);
+
+struct SharedPools {
+    generation: u64,
+    opts: Opts,
+    dump_opts: Option<Opts>,
+    pool: Pool,
+    dump_pool: Option<Pool>,
+}
+
+impl SharedPools {
+    async fn disconnect(self) {
+        if let Err(e) = self.pool.disconnect().await {
+            warn!("Failed to close pooled connections: {}", e);
+        }
+        if let Some(pool) = self.dump_pool {
+            if let Err(e) = pool.disconnect().await {
+                warn!("Failed to close pooled backup host connections: {}", e);
+            }
+        }
+    }
+}
+
+/// Closes the pools shared between jobs, e.g. before the process exits.
+pub async fn close_shared_pools() {
+    let pools = std::mem::take(&mut *SHARED_POOLS.lock().unwrap_or_else(|e| e.into_inner()));
+    for (_, pools) in pools {
+        pools.disconnect().await;
+    }
+}
+
+/// Column types whose values the dumper cannot write back faithfully.
+const UNSUPPORTED_TYPES: &[&str] = &[
+    "bit",
+    "geometry",
+    "point",
+    "linestring",
+    "polygon",
+    "multipoint",
+    "multilinestring",
+    "multipolygon",
+    "geometrycollection",
+    "geomcollection",
+    "vector",
+];
+
+/// Counts the bytes of a dump, so checkpoints can record where each table's output ends.
+struct CountingWriter<W> {
+    inner: W,
+    written: u64,
+}
+
+impl<W: Write> Write for CountingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.written += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
 pub struct MysqlDriver {
     pool: Pool,
+    dump_pool: Option<Pool>,
     config: DatabaseConfig,
+    pk_ranges: Vec<PkRange>,
+    dump_format: DumpFormat,
+    /// Generation of the shared pools this driver runs on; `None` when it owns its pools.
+    shared: Option<u64>,
 }
 
 impl MysqlDriver {
     pub fn new(config: &DatabaseConfig) -> Result<Self> {
-        let opts: Opts = OptsBuilder::default()
-            .ip_or_hostname(&config.host)
-            .tcp_port(config.port)
-            .user(Some(&config.username))
-            .pass(Some(&config.password))
-            .into();
+        let config = &resolve_credentials(config)?;
+        let pool = Pool::new(Self::opts(config, &config.host, config.port));
+        let dump_pool = config.backup_host.as_ref().map(|host| {
+            let port = config.backup_port.unwrap_or(config.port);
+            Pool::new(Self::opts(config, host, port))
+        });
 
-        let pool = Pool::new(opts);
-        
         Ok(Self {
             pool,
+            dump_pool,
             config: config.clone(),
+            pk_ranges: Vec::new(),
+            dump_format: DumpFormat::Sql,
+            shared: None,
         })
     }
+    /// Driver on the pools shared by all jobs of the connection, so consecutive jobs
+    /// on one server reuse connections. The pools are replaced when the connection's
+    /// settings change, and `close` leaves them open.
+    pub fn shared(config: &DatabaseConfig) -> Result<Self> {
+        let config = &resolve_credentials(config)?;
+        let opts = Self::shared_opts(config, &config.host, config.port);
+        let dump_opts = config.backup_host.as_ref().map(|host| {
+            Self::shared_opts(config, host, config.backup_port.unwrap_or(config.port))
+        });
+
+        let mut pools = SHARED_POOLS.lock().unwrap_or_else(|e| e.into_inner());
+        let (generation, pool, dump_pool) = match pools.get(&config.name) {
+            Some(cached) if cached.opts == opts && cached.dump_opts == dump_opts => {
+                (cached.generation, cached.pool.clone(), cached.dump_pool.clone())
+            }
+            _ => {
+                let fresh = SharedPools {
+                    generation: NEXT_POOL_GENERATION.fetch_add(1, Ordering::Relaxed),
+                    pool: Pool::new(opts.clone()),
+                    dump_pool: dump_opts.clone().map(Pool::new),
+                    opts,
+                    dump_opts,
+                };
+                let handles = (fresh.generation, fresh.pool.clone(), fresh.dump_pool.clone());
+                // Jobs still running may hold clones of a stale pool, so it is only dropped
+                // from the cache; its connections close once the last clone is gone.
+                if pools.insert(config.name.clone(), fresh).is_some() {
+                    debug!("Connection settings of '{}' changed, replacing its pool", config.name);
+                }
+                handles
+            }
+        };
+
+        Ok(Self {
+            pool,
+            dump_pool,
+            config: config.clone(),
+            pk_ranges: Vec::new(),
+            dump_format: DumpFormat::Sql,
+            shared: Some(generation),
+        })
+    }
+
+    fn builder(config: &DatabaseConfig, host: &str, port: u16) -> OptsBuilder {
+        OptsBuilder::default()
+            .ip_or_hostname(host)
+            .tcp_port(port)
+            .user(Some(&config.username))
+            .pass(Some(&config.password))
+            .enable_cleartext_plugin(config.enable_cleartext_plugin)
+            .secure_auth(config.secure_auth)
+    }
+
+    fn opts(config: &DatabaseConfig, host: &str, port: u16) -> Opts {
+        Self::builder(config, host, port).into()
+    }
+
+    fn shared_opts(config: &DatabaseConfig, host: &str, port: u16) -> Opts {
+        let constraints = PoolConstraints::new(0, 100).expect("valid pool constraints");
+        let pool_opts = PoolOpts::default()
+            .with_constraints(constraints)
+            .with_inactive_connection_ttl(Duration::from_secs(SHARED_POOL_IDLE_SECS));
+        Self::builder(config, host, port).pool_opts(pool_opts).into()
+    }
+
     async fn get_conn(&self) -> Result<Conn> {
         self.pool.get_conn().await.map_err(BackupError::from)
+    }
+
+    /// Connection for the heavy dump queries: the backup host when one is configured.
+    async fn get_dump_conn(&self) -> Result<Conn> {
+        match &self.dump_pool {
+            Some(pool) => pool.get_conn().await.map_err(BackupError::from),
+            None => self.get_conn().await,
+        }
     }
     fn escape_string(s: &str) -> String {
         s.replace('\\', "\\\\")
@@ -37,98 +201,766 @@ impl MysqlDriver {
             .replace('\n', "\\n")
             .replace('\r', "\\r")
             .replace('\0', "\\0")
+    }
+
+    /// Quotes JSON text as it came from the server. Double quotes are left alone
+    /// so the document MySQL parses on restore is byte-for-byte the one dumped.
+    fn json_literal(json: &str) -> String {
+        let mut literal = String::with_capacity(json.len() + 2);
+        literal.push('\'');
+        for c in json.chars() {
+            match c {
+                '\\' => literal.push_str("\\\\"),
+                '\'' => literal.push_str("\\'"),
+                '\n' => literal.push_str("\\n"),
+                '\r' => literal.push_str("\\r"),
+                '\0' => literal.push_str("\\0"),
+                c => literal.push(c),
+            }
+        }
+        literal.push('\'');
+        literal
+    }
+
+    fn format_value(value: mysql_async::Value) -> Option<String> {
+        match value {
+            mysql_async::Value::NULL => Some("NULL".to_string()),
+            mysql_async::Value::Bytes(bytes) => match String::from_utf8(bytes) {
+                Ok(s) => Some(format!("'{}'", Self::escape_string(&s))),
+                Err(e) => Some(format!("X'{}'", hex::encode(e.as_bytes()))),
+            },
+            mysql_async::Value::Int(n) => Some(n.to_string()),
+            mysql_async::Value::UInt(n) => Some(n.to_string()),
+            mysql_async::Value::Float(n) => Some(n.to_string()),
+            mysql_async::Value::Double(n) => Some(n.to_string()),
+            mysql_async::Value::Date(y, m, d, h, mi, s, us) => Some(format!(
+                "'{:04}-{:02}-{:02} {:02}:{:02}:{:02}.{:06}'",
+                y, m, d, h, mi, s, us
+            )),
+            mysql_async::Value::Time(neg, d, h, m, s, us) => {
+                let sign = if neg { "-" } else { "" };
+                Some(format!("'{}{}:{:02}:{:02}.{:06}'", sign, d * 24 + h as u32, m, s, us))
+            }
+        }
+    }
+
+    /// Whether an `information_schema` DATA_TYPE holds raw bytes rather than text.
+    fn is_binary_type(data_type: &str) -> bool {
+        matches!(
+            data_type.to_ascii_lowercase().as_str(),
+            "binary" | "varbinary" | "tinyblob" | "blob" | "mediumblob" | "longblob"
+        )
+    }
+
+    /// Writes a large value as a hex literal without materializing the whole encoded string.
+    fn write_hex_chunked<W: Write>(writer: &mut W, bytes: &[u8]) -> Result<()> {
+        writer.write_all(b"X'")?;
+        for chunk in bytes.chunks(HEX_CHUNK_SIZE) {
+            writer.write_all(hex::encode(chunk).as_bytes())?;
+        }
+        writer.write_all(b"'")?;
+        Ok(())
+    }
+
+    /// Session settings that keep the server from dropping the connection during a
+    /// long dump (e.g. while the writer is blocked on a slow disk). 0 keeps the server value.
+    fn session_timeouts(config: &DatabaseConfig) -> Option<String> {
+        let settings: Vec<String> = [
+            ("wait_timeout", config.wait_timeout_secs),
+            ("net_write_timeout", config.net_write_timeout_secs),
+        ]
+        .iter()
+        .filter(|(_, secs)| *secs > 0)
+        .map(|(name, secs)| format!("SESSION {} = {}", name, secs))
+        .collect();
+
+        if settings.is_empty() {
+            None
+        } else {
+            Some(format!("SET {}", settings.join(", ")))
+        }
     }
+    /// `SET` statements that must apply both to the dump connection and, written into
+    /// the dump, to the restoring session, so values are read and written the same way.
+    fn session_settings(config: &DatabaseConfig) -> Vec<String> {
+        let mut settings = Vec::new();
+        if let Some(names) = &config.set_names {
+            settings.push(format!("SET NAMES '{}'", Self::escape_string(names)));
+        }
+        if let Some(time_zone) = &config.set_time_zone {
+            settings.push(format!("SET TIME_ZONE='{}'", Self::escape_string(time_zone)));
+        }
+        settings
+    }
+
+    fn dump_preamble(config: &DatabaseConfig) -> String {
+        let mut preamble = String::new();
+        for statement in Self::session_settings(config) {
+            preamble.push_str(&statement);
+            preamble.push_str(";\n");
+        }
+        preamble.push_str("SET FOREIGN_KEY_CHECKS=0;\n");
+        if config.disable_unique_checks {
+            preamble.push_str("SET UNIQUE_CHECKS=0;\n");
+        }
+        preamble.push_str("SET SQL_MODE='NO_AUTO_VALUE_ON_ZERO';\n\n");
+        preamble
+    }
+
+    fn dump_postamble(config: &DatabaseConfig) -> String {
+        let mut postamble = String::from("\nSET FOREIGN_KEY_CHECKS=1;\n");
+        if config.disable_unique_checks {
+            postamble.push_str("SET UNIQUE_CHECKS=1;\n");
+        }
+        postamble
+    }
+
     async fn get_create_table(&self, conn: &mut Conn, db_name: &str, table: &str) -> Result<String> {
         let query = format!("SHOW CREATE TABLE `{}`.`{}`", db_name, table);
         let row: Option<Row> = conn.query_first(&query).await?;
         
         if let Some(row) = row {
             let create_stmt: String = row.get(1).unwrap_or_default();
+            if self.config.no_tablespaces || self.config.minimal_privileges {
+                return Ok(Self::strip_tablespace(&create_stmt));
+            }
             Ok(create_stmt)
         } else {
-            Err(BackupError::Database(format!(
+            Err(BackupError::database(format!(
                 "Could not get CREATE TABLE for {}.{}",
                 db_name, table
             )))
         }
+    }
+
+    /// Drops the `/*!50100 TABLESPACE ... */` clause SHOW CREATE TABLE adds for tables in
+    /// a general tablespace, which would fail to restore where it doesn't exist.
+    fn strip_tablespace(create_stmt: &str) -> String {
+        let mut stmt = create_stmt.to_string();
+        while let Some(found) = stmt.find("/*!50100 TABLESPACE") {
+            let Some(len) = stmt[found..].find("*/") else {
+                break;
+            };
+            let start = if stmt[..found].ends_with(' ') { found - 1 } else { found };
+            stmt.replace_range(start..found + len + 2, "");
+        }
+        stmt
+    }
+
+    /// The configured lock, minus the global one, which needs RELOAD, in minimal-privileges mode.
+    fn table_lock(&self) -> TableLock {
+        match self.config.lock_tables {
+            TableLock::Global if self.config.minimal_privileges => TableLock::Tables,
+            lock => lock,
+        }
     }
+    async fn collect_warnings(&self, conn: &mut Conn, context: &str, warnings: &mut Vec<String>) -> Result<()> {
+        if !self.config.show_warnings {
+            return Ok(());
+        }
+        let rows: Vec<(String, u32, String)> = conn.query("SHOW WARNINGS").await?;
+        for (level, code, message) in rows {
+            let warning = format!("{}: {} {}: {}", context, level, code, message);
+            warn!("MySQL warning during dump of {}", warning);
+            warnings.push(warning);
+        }
+        Ok(())
+    }
+
+    async fn binlog_position(&self, conn: &mut Conn) -> Result<Option<BinlogPosition>> {
+        // MySQL 8.2 renamed SHOW MASTER STATUS; older servers and MariaDB only know the old form.
+        let row: Option<Row> = match conn.query_first("SHOW BINARY LOG STATUS").await {
+            Ok(row) => row,
+            Err(_) => conn.query_first("SHOW MASTER STATUS").await?,
+        };
+        let Some(row) = row else {
+            warn!("Binary logging is disabled on {}, no binlog position recorded", self.config.name);
+            return Ok(None);
+        };
+
+        let file: String = row.get(0).unwrap_or_default();
+        let position: u64 = row.get(1).unwrap_or_default();
+        let gtid_executed: Option<String> = conn
+            .query_first("SELECT @@GLOBAL.gtid_executed")
+            .await
+            .ok()
+            .flatten()
+            .filter(|gtid: &String| !gtid.is_empty());
+
+        Ok(Some(BinlogPosition {
+            file,
+            position,
+            gtid_executed,
+        }))
+    }
+
+    /// Starts the consistent snapshot, if any, and reads the binlog position at that point.
+    async fn start_snapshot(&self, conn: &mut Conn, snapshot: bool, read_position: bool) -> Result<Option<BinlogPosition>> {
+        if snapshot {
+            conn.query_drop("SET SESSION TRANSACTION ISOLATION LEVEL REPEATABLE READ").await?;
+            conn.query_drop("START TRANSACTION WITH CONSISTENT SNAPSHOT").await?;
+        }
+        if read_position {
+            self.binlog_position(conn).await
+        } else {
+            Ok(None)
+        }
+    }
+
+    async fn lock_tables(&self, conn: &mut Conn, db_name: &str, tables: &[String]) -> Result<bool> {
+        if self.table_lock() != self.config.lock_tables {
+            warn!("minimal_privileges is set on {}, using per-table locks instead of a global lock", self.config.name);
+        }
+        match self.table_lock() {
+            TableLock::None => Ok(false),
+            TableLock::Tables if tables.is_empty() => Ok(false),
+            TableLock::Tables => {
+                warn!("Locking tables in {} for reading; writes are blocked until the dump completes", db_name);
+                let list = tables
+                    .iter()
+                    .map(|t| format!("`{}`.`{}` READ", db_name, t))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                conn.query_drop(format!("LOCK TABLES {}", list)).await?;
+                Ok(true)
+            }
+            TableLock::Global => {
+                warn!("Acquiring a global read lock on {}; writes are blocked until the dump completes", self.config.name);
+                conn.query_drop("FLUSH TABLES WITH READ LOCK").await?;
+                Ok(true)
+            }
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn dump_tables<W: Write + Send>(
+        &self,
+        conn: &mut Conn,
+        db_name: &str,
+        tables: &[String],
+        writer: &mut CountingWriter<W>,
+        report: &mut DumpReport,
+        verbosity: Verbosity,
+        progress: Option<ProgressCallback>,
+        mut checkpoint: DumpCheckpoint,
+        on_checkpoint: Option<CheckpointCallback>,
+    ) -> Result<()> {
+        // START TRANSACTION releases LOCK TABLES, so with table locks the locks
+        // provide consistency instead of a snapshot.
+        let snapshot = self.config.record_binlog_position && self.table_lock() != TableLock::Tables;
+        // SHOW MASTER STATUS needs REPLICATION CLIENT; the snapshot alone still keeps the dump consistent.
+        let read_position = self.config.record_binlog_position && !self.config.minimal_privileges;
+        // A write committed between starting the snapshot and reading the position would
+        // be missing from the dump yet come before the position. Like mysqldump, hold a
+        // global read lock for just those two steps unless the dump already holds one.
+        let brief_lock = snapshot && read_position && self.table_lock() == TableLock::None;
+        if brief_lock {
+            conn.query_drop("FLUSH TABLES WITH READ LOCK").await?;
+        }
+        let started = self.start_snapshot(conn, snapshot, read_position).await;
+        if brief_lock {
+            // Released even when the snapshot failed, so writes aren't left blocked.
+            conn.query_drop("UNLOCK TABLES").await?;
+        }
+        report.binlog_position = started?;
+
+        if self.config.record_binlog_position && self.config.minimal_privileges {
+            report.warnings.push(format!(
+                "{}: binlog position not recorded, minimal_privileges skips SHOW MASTER STATUS",
+                db_name
+            ));
+        } else if self.config.record_binlog_position {
+            if let (Some(pos), DumpFormat::Sql) = (&report.binlog_position, self.dump_format) {
+                let mut comment = format!(
+                    "-- Binlog position at snapshot:\n\
+                     -- CHANGE MASTER TO MASTER_LOG_FILE='{}', MASTER_LOG_POS={};\n",
+                    pos.file, pos.position
+                );
+                if let Some(gtid) = &pos.gtid_executed {
+                    comment.push_str(&format!("-- SET @@GLOBAL.GTID_PURGED='{}';\n", gtid));
+                }
+                comment.push('\n');
+                writer.write_all(comment.as_bytes())?;
+            }
+        }
+
+        for (index, table) in tables.iter().enumerate() {
+            if checkpoint.tables.contains(table) {
+                continue;
+            }
+            if verbosity.is_debug() {
+                info!("Dumping table {}/{} ({}/{})", db_name, table, index + 1, tables.len());
+            }
+            if let Some(callback) = &progress {
+                callback(DumpProgress {
+                    database: db_name.to_string(),
+                    table: table.clone(),
+                    table_index: index + 1,
+                    table_count: tables.len(),
+                    database_index: 1,
+                    database_count: 1,
+                });
+            }
+            if self.dump_format == DumpFormat::Ndjson {
+                self.dump_table_ndjson(conn, db_name, table, writer, &mut report.warnings).await?;
+            } else {
+                let table_header = format!("\n-- Table: {}\n-- ----------------------------------------\n\n", table);
+                writer.write_all(table_header.as_bytes())?;
+                let create_stmt = self.get_create_table(conn, db_name, table).await?;
+                if self.config.insert_mode == InsertMode::Insert {
+                    let drop_stmt = format!("DROP TABLE IF EXISTS `{}`;\n\n", table);
+                    writer.write_all(drop_stmt.as_bytes())?;
+                    writer.write_all(create_stmt.as_bytes())?;
+                } else {
+                    // Merge dumps keep existing tables and rows in place.
+                    let create_stmt = create_stmt.replacen("CREATE TABLE", "CREATE TABLE IF NOT EXISTS", 1);
+                    writer.write_all(create_stmt.as_bytes())?;
+                }
+                writer.write_all(b";\n\n")?;
+                self.dump_table_data(conn, db_name, table, writer, &mut report.warnings).await?;
+            }
+            if let Some(callback) = &on_checkpoint {
+                writer.flush()?;
+                checkpoint.tables.push(table.clone());
+                checkpoint.offset = writer.written;
+                callback(&checkpoint);
+            }
+        }
+        if self.dump_format == DumpFormat::Sql {
+            writer.write_all(Self::dump_postamble(&self.config).as_bytes())?;
+        }
+        if snapshot {
+            conn.query_drop("COMMIT").await?;
+        }
+        Ok(())
+    }
+
+    async fn check_column_types(
+        &self,
+        conn: &mut Conn,
+        db_name: &str,
+        tables: &[String],
+        warnings: &mut Vec<String>,
+    ) -> Result<()> {
+        let columns: Vec<(String, String, String)> = conn
+            .exec(
+                "SELECT TABLE_NAME, COLUMN_NAME, DATA_TYPE FROM INFORMATION_SCHEMA.COLUMNS \
+                 WHERE TABLE_SCHEMA = ? ORDER BY TABLE_NAME, ORDINAL_POSITION",
+                (db_name,),
+            )
+            .await?;
+
+        let unsupported: Vec<String> = columns
+            .into_iter()
+            .filter(|(table, _, data_type)| {
+                tables.contains(table) && UNSUPPORTED_TYPES.contains(&data_type.to_lowercase().as_str())
+            })
+            .map(|(table, column, data_type)| {
+                format!("{}.{}: column `{}` has type {} which may not restore faithfully", db_name, table, column, data_type)
+            })
+            .collect();
+        if unsupported.is_empty() {
+            return Ok(());
+        }
+
+        if self.config.unsupported_types == UnsupportedTypePolicy::Fail {
+            return Err(BackupError::database(unsupported.join("; ")));
+        }
+        for message in unsupported {
+            warn!("{}", message);
+            warnings.push(message);
+        }
+        Ok(())
+    }
+
     async fn get_tables(&self, conn: &mut Conn, db_name: &str) -> Result<Vec<String>> {
         let query = format!("SHOW TABLES FROM `{}`", db_name);
         let tables: Vec<String> = conn.query(query).await?;
         Ok(tables)
     }
+    /// The column a `pk_ranges` entry filters on: the one it names, which must lead an
+    /// index, or else the table's single-column primary key.
+    async fn range_column(&self, conn: &mut Conn, db_name: &str, table: &str, range: &PkRange) -> Result<String> {
+        if let Some(column) = &range.column {
+            let indexed: Option<u64> = conn
+                .exec_first(
+                    "SELECT COUNT(*) FROM INFORMATION_SCHEMA.STATISTICS WHERE TABLE_SCHEMA = ? AND TABLE_NAME = ? AND COLUMN_NAME = ? AND SEQ_IN_INDEX = 1",
+                    (db_name, table, column),
+                )
+                .await?;
+            if indexed.unwrap_or(0) == 0 {
+                return Err(BackupError::Config(format!(
+                    "pk_ranges column `{}` is not a key of {}.{}",
+                    column, db_name, table
+                )));
+            }
+            return Ok(column.clone());
+        }
+
+        let primary: Vec<String> = conn
+            .exec(
+                "SELECT COLUMN_NAME FROM INFORMATION_SCHEMA.KEY_COLUMN_USAGE WHERE TABLE_SCHEMA = ? AND TABLE_NAME = ? AND CONSTRAINT_NAME = 'PRIMARY' ORDER BY ORDINAL_POSITION",
+                (db_name, table),
+            )
+            .await?;
+        match &primary[..] {
+            [column] => Ok(column.clone()),
+            [] => Err(BackupError::Config(format!(
+                "{}.{} has no primary key; set `column` in its pk_ranges entry",
+                db_name, table
+            ))),
+            _ => Err(BackupError::Config(format!(
+                "{}.{} has a composite primary key; set `column` in its pk_ranges entry",
+                db_name, table
+            ))),
+        }
+    }
+
+    fn range_condition(column: &str, range: &PkRange) -> String {
+        format!("`{}` BETWEEN {} AND {}", column.replace('`', "``"), range.min, range.max)
+    }
+
+    /// How long to pause so that `rows` read since `elapsed` stays under `rows_per_sec`.
+    fn throttle_delay(rows_per_sec: u64, rows: u64, elapsed: Duration) -> Option<Duration> {
+        if rows_per_sec == 0 {
+            return None;
+        }
+        let target = Duration::from_secs_f64(rows as f64 / rows_per_sec as f64);
+        target.checked_sub(elapsed).filter(|delay| !delay.is_zero())
+    }
+
+    async fn throttle(&self, started: Instant, rows: u64) {
+        let Some(rows_per_sec) = self.config.throttle_rows_per_sec else {
+            return;
+        };
+        if let Some(delay) = Self::throttle_delay(rows_per_sec, rows, started.elapsed()) {
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    /// Column names and data types of a table, in table order.
+    async fn column_types(&self, conn: &mut Conn, db_name: &str, table: &str) -> Result<Vec<(String, String)>> {
+        let columns_query = format!(
+            "SELECT COLUMN_NAME, DATA_TYPE FROM INFORMATION_SCHEMA.COLUMNS WHERE TABLE_SCHEMA = '{}' AND TABLE_NAME = '{}' ORDER BY ORDINAL_POSITION",
+            db_name, table
+        );
+        Ok(conn.query(columns_query).await?)
+    }
+
     async fn dump_table_data<W: Write + Send>(
         &self,
         conn: &mut Conn,
         db_name: &str,
         table: &str,
         writer: &mut W,
+        warnings: &mut Vec<String>,
     ) -> Result<()> {
-        let columns_query = format!(
-            "SELECT COLUMN_NAME FROM INFORMATION_SCHEMA.COLUMNS WHERE TABLE_SCHEMA = '{}' AND TABLE_NAME = '{}' ORDER BY ORDINAL_POSITION",
-            db_name, table
-        );
-        let columns: Vec<String> = conn.query(columns_query).await?;
-        
+        let column_types = self.column_types(conn, db_name, table).await?;
+        let columns: Vec<String> = column_types.iter().map(|(name, _)| name.clone()).collect();
+        let json_columns: Vec<bool> = column_types
+            .iter()
+            .map(|(_, data_type)| data_type.eq_ignore_ascii_case("json"))
+            .collect();
+        let hex_columns: Vec<bool> = column_types
+            .iter()
+            .map(|(_, data_type)| self.config.hex_blob && Self::is_binary_type(data_type))
+            .collect();
+
         if columns.is_empty() {
             return Ok(());
         }
-        let select_query = format!("SELECT * FROM `{}`.`{}`", db_name, table);
-        let rows: Vec<Row> = conn.query(select_query).await?;
-
-        if rows.is_empty() {
-            return Ok(());
+        let mut select_query = format!("SELECT * FROM `{}`.`{}`", db_name, table);
+        if let Some(range) = self.pk_ranges.iter().find(|r| r.applies_to(db_name, table)) {
+            let column = self.range_column(conn, db_name, table, range).await?;
+            let condition = Self::range_condition(&column, range);
+            writeln!(writer, "-- Only rows WHERE {}\n", condition)?;
+            select_query.push_str(&format!(" WHERE {}", condition));
         }
-        let batch_size = 100;
-        for chunk in rows.chunks(batch_size) {
-            let mut insert = format!(
-                "INSERT INTO `{}` ({}) VALUES\n",
-                table,
-                columns.iter().map(|c| format!("`{}`", c)).collect::<Vec<_>>().join(", ")
-            );
+        let mut null_fallbacks = vec![0usize; columns.len()];
+        let mut skipped_large = vec![0usize; columns.len()];
+        let threshold = self.config.large_value_threshold();
+        let insert_mode = self.config.insert_mode;
+        // Without extended inserts each row is a single-line statement, so dumps diff per row.
+        let (batch_size, values_separator, terminator) = if self.config.extended_inserts {
+            (100, "\n", ";\n\n")
+        } else {
+            (1, " ", ";\n")
+        };
+        let on_duplicate = match insert_mode {
+            InsertMode::OnDuplicateKeyUpdate => format!(
+                "{}ON DUPLICATE KEY UPDATE {}",
+                values_separator,
+                columns
+                    .iter()
+                    .map(|c| format!("`{0}` = VALUES(`{0}`)", c))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            _ => String::new(),
+        };
 
-            let values: Vec<String> = chunk
-                .iter()
-                .map(|row| {
-                    let vals: Vec<String> = (0..columns.len())
-                        .map(|i| {
-                            match row.get_opt::<mysql_async::Value, _>(i) {
-                                Some(Ok(mysql_async::Value::NULL)) => "NULL".to_string(),
-                                Some(Ok(mysql_async::Value::Bytes(bytes))) => {
-                                    match String::from_utf8(bytes.clone()) {
-                                        Ok(s) => format!("'{}'", Self::escape_string(&s)),
-                                        Err(_) => {
-                                            format!("X'{}'", hex::encode(&bytes))
-                                        }
+        // Rows are streamed rather than collected, so throttling holds back the server's
+        // reads instead of only our writes.
+        let mut result = conn.query_iter(select_query).await?;
+        let mut batch: Vec<Row> = Vec::with_capacity(batch_size);
+        let mut row_count = 0u64;
+        let started = Instant::now();
+        loop {
+            let next = result.next().await?;
+            let done = next.is_none();
+            batch.extend(next);
+            if batch.len() < batch_size && !done {
+                continue;
+            }
+            if !batch.is_empty() {
+                write!(
+                    writer,
+                    "{} INTO `{}` ({}) VALUES{}",
+                    insert_mode.keyword(),
+                    table,
+                    columns.iter().map(|c| format!("`{}`", c)).collect::<Vec<_>>().join(", "),
+                    values_separator
+                )?;
+
+                // Values are written one at a time so an oversized BLOB never has to be
+                // escaped into a single in-memory string alongside the rest of the batch.
+                for (row_idx, row) in batch.iter().enumerate() {
+                    writer.write_all(if row_idx == 0 { b"(" } else { b",\n(" })?;
+                    for i in 0..columns.len() {
+                        if i > 0 {
+                            writer.write_all(b", ")?;
+                        }
+                        match row.as_ref(i) {
+                            // JSON columns reject binary strings, so they never take the hex path.
+                            Some(mysql_async::Value::Bytes(bytes)) if json_columns[i] => {
+                                match std::str::from_utf8(bytes) {
+                                    Ok(json) => writer.write_all(Self::json_literal(json).as_bytes())?,
+                                    Err(_) => {
+                                        null_fallbacks[i] += 1;
+                                        writer.write_all(b"NULL")?;
                                     }
                                 }
-                                Some(Ok(mysql_async::Value::Int(n))) => n.to_string(),
-                                Some(Ok(mysql_async::Value::UInt(n))) => n.to_string(),
-                                Some(Ok(mysql_async::Value::Float(n))) => n.to_string(),
-                                Some(Ok(mysql_async::Value::Double(n))) => n.to_string(),
-                                Some(Ok(mysql_async::Value::Date(y, m, d, h, mi, s, us))) => {
-                                    format!("'{:04}-{:02}-{:02} {:02}:{:02}:{:02}.{:06}'", y, m, d, h, mi, s, us)
-                                }
-                                Some(Ok(mysql_async::Value::Time(neg, d, h, m, s, us))) => {
-                                    let sign = if neg { "-" } else { "" };
-                                    format!("'{}{}:{:02}:{:02}.{:06}'", sign, d * 24 + h as u32, m, s, us)
+                                continue;
+                            }
+                            Some(mysql_async::Value::Bytes(bytes)) if bytes.len() > threshold => {
+                                match self.config.large_value_policy {
+                                    LargeValuePolicy::Stream => Self::write_hex_chunked(writer, bytes)?,
+                                    LargeValuePolicy::Skip => {
+                                        skipped_large[i] += 1;
+                                        writer.write_all(b"NULL")?;
+                                    }
                                 }
-                                Some(Err(_)) | None => "NULL".to_string(),
+                                continue;
+                            }
+                            Some(mysql_async::Value::Bytes(bytes)) if hex_columns[i] => {
+                                Self::write_hex_chunked(writer, bytes)?;
+                                continue;
                             }
-                        })
-                        .collect();
-                    format!("({})", vals.join(", "))
-                })
-                .collect();
+                            _ => {}
+                        }
+                        let value = match row.get_opt::<mysql_async::Value, _>(i) {
+                            Some(Ok(value)) => Self::format_value(value),
+                            Some(Err(_)) | None => None,
+                        };
+                        match value {
+                            Some(value) => writer.write_all(value.as_bytes())?,
+                            None => {
+                                null_fallbacks[i] += 1;
+                                writer.write_all(b"NULL")?;
+                            }
+                        }
+                    }
+                    writer.write_all(b")")?;
+                }
+                writer.write_all(on_duplicate.as_bytes())?;
+                writer.write_all(terminator.as_bytes())?;
+                row_count += batch.len() as u64;
+                batch.clear();
+                self.throttle(started, row_count).await;
+            }
+            if done {
+                break;
+            }
+        }
+        drop(result);
+        self.collect_warnings(conn, &format!("{}.{}", db_name, table), warnings).await?;
+        if row_count == 0 {
+            return Ok(());
+        }
+        if !self.config.extended_inserts {
+            writer.write_all(b"\n")?;
+        }
 
-            insert.push_str(&values.join(",\n"));
-            insert.push_str(";\n\n");
+        for (column, count) in columns.iter().zip(&skipped_large) {
+            if *count == 0 {
+                continue;
+            }
+            let message = format!(
+                "{}.{}: {} value(s) in column `{}` exceeded {} MB and were written as NULL",
+                db_name, table, count, column, self.config.large_value_threshold_mb
+            );
+            warn!("{}", message);
+            warnings.push(message);
+        }
 
-            writer.write_all(insert.as_bytes())?;
+        for (column, count) in columns.iter().zip(&null_fallbacks) {
+            if *count == 0 {
+                continue;
+            }
+            let message = format!(
+                "{}.{}: {} value(s) in column `{}` could not be converted and were written as NULL",
+                db_name, table, count, column
+            );
+            if self.config.null_fallback == NullFallback::Fail {
+                return Err(BackupError::database(message));
+            }
+            warn!("{}", message);
+            warnings.push(message);
         }
 
         Ok(())
     }
+
+    /// Dumps a table as NDJSON: a line describing the table, then one object per row.
+    async fn dump_table_ndjson<W: Write + Send>(
+        &self,
+        conn: &mut Conn,
+        db_name: &str,
+        table: &str,
+        writer: &mut W,
+        warnings: &mut Vec<String>,
+    ) -> Result<()> {
+        let column_types = self.column_types(conn, db_name, table).await?;
+        let create_stmt = self.get_create_table(conn, db_name, table).await?;
+        let mut schema = serde_json::json!({
+            "columns": column_types
+                .iter()
+                .map(|(name, data_type)| serde_json::json!({ "name": name, "type": data_type }))
+                .collect::<Vec<_>>(),
+            "create_table": create_stmt,
+        });
+
+        let mut select_query = format!("SELECT * FROM `{}`.`{}`", db_name, table);
+        if let Some(range) = self.pk_ranges.iter().find(|r| r.applies_to(db_name, table)) {
+            let column = self.range_column(conn, db_name, table, range).await?;
+            let condition = Self::range_condition(&column, range);
+            schema["where"] = serde_json::Value::String(condition.clone());
+            select_query.push_str(&format!(" WHERE {}", condition));
+        }
+        serde_json::to_writer(&mut *writer, &serde_json::json!({ "table": table, "schema": schema }))?;
+        writer.write_all(b"\n")?;
+
+        let mut skipped_large = vec![0usize; column_types.len()];
+        let threshold = self.config.large_value_threshold();
+        // Rows are written field by field, keys sorted as a JSON map would have them, so an
+        // oversized value goes straight to the writer instead of into a `serde_json::Value`.
+        let mut order: Vec<usize> = (0..column_types.len()).collect();
+        order.sort_by(|a, b| column_types[*a].0.cmp(&column_types[*b].0));
+        let mut result = conn.query_iter(select_query).await?;
+        let mut row_count = 0u64;
+        let started = Instant::now();
+        while let Some(row) = result.next().await? {
+            writer.write_all(b"{\"row\":{")?;
+            for (n, &i) in order.iter().enumerate() {
+                let (column, data_type) = &column_types[i];
+                if n > 0 {
+                    writer.write_all(b",")?;
+                }
+                serde_json::to_writer(&mut *writer, column)?;
+                writer.write_all(b":")?;
+                match row.as_ref(i) {
+                    Some(mysql_async::Value::Bytes(bytes)) if bytes.len() > threshold => {
+                        match self.config.large_value_policy {
+                            LargeValuePolicy::Stream => Self::write_ndjson_large(writer, bytes, data_type)?,
+                            LargeValuePolicy::Skip => {
+                                skipped_large[i] += 1;
+                                writer.write_all(b"null")?;
+                            }
+                        }
+                    }
+                    Some(value) => serde_json::to_writer(&mut *writer, &Self::ndjson_value(value, data_type))?,
+                    None => writer.write_all(b"null")?,
+                }
+            }
+            writer.write_all(b"},\"table\":")?;
+            serde_json::to_writer(&mut *writer, table)?;
+            writer.write_all(b"}\n")?;
+            row_count += 1;
+            if row_count % NDJSON_THROTTLE_ROWS == 0 {
+                self.throttle(started, row_count).await;
+            }
+        }
+        drop(result);
+        self.collect_warnings(conn, &format!("{}.{}", db_name, table), warnings).await?;
+
+        for ((column, _), count) in column_types.iter().zip(&skipped_large) {
+            if *count == 0 {
+                continue;
+            }
+            let message = format!(
+                "{}.{}: {} value(s) in column `{}` exceeded {} MB and were written as null",
+                db_name, table, count, column, self.config.large_value_threshold_mb
+            );
+            warn!("{}", message);
+            warnings.push(message);
+        }
+        Ok(())
+    }
+
+    /// Writes a value over `large_value_threshold_mb` as [`Self::ndjson_value`] would, without
+    /// copying it: hex in chunks for binary data, escaped text, and JSON documents as the
+    /// server returned them.
+    fn write_ndjson_large<W: Write>(writer: &mut W, bytes: &[u8], data_type: &str) -> Result<()> {
+        let text = if Self::is_binary_type(data_type) { None } else { std::str::from_utf8(bytes).ok() };
+        match text {
+            Some(json) if data_type.eq_ignore_ascii_case("json") => writer.write_all(json.as_bytes())?,
+            Some(text) => serde_json::to_writer(&mut *writer, text)?,
+            None => {
+                writer.write_all(b"\"")?;
+                for chunk in bytes.chunks(HEX_CHUNK_SIZE) {
+                    writer.write_all(hex::encode(chunk).as_bytes())?;
+                }
+                writer.write_all(b"\"")?;
+            }
+        }
+        Ok(())
+    }
+
+    /// A value as JSON, typed by its column since the text protocol returns everything
+    /// as bytes. DECIMAL stays a string to keep its precision; binary columns and text
+    /// that isn't valid UTF-8 become hex strings.
+    fn ndjson_value(value: &mysql_async::Value, data_type: &str) -> serde_json::Value {
+        use serde_json::Value as Json;
+        let bytes = match value {
+            mysql_async::Value::NULL => return Json::Null,
+            mysql_async::Value::Int(n) => return Json::from(*n),
+            mysql_async::Value::UInt(n) => return Json::from(*n),
+            mysql_async::Value::Float(n) => return Json::from(*n),
+            mysql_async::Value::Double(n) => return Json::from(*n),
+            mysql_async::Value::Bytes(bytes) => bytes,
+            other => {
+                return Self::format_value(other.clone())
+                    .map_or(Json::Null, |s| Json::String(s.trim_matches('\'').to_string()))
+            }
+        };
+        if Self::is_binary_type(data_type) {
+            return Json::String(hex::encode(bytes));
+        }
+        let Ok(text) = std::str::from_utf8(bytes) else {
+            return Json::String(hex::encode(bytes));
+        };
+        let parsed = match data_type.to_ascii_lowercase().as_str() {
+            "json" => serde_json::from_str(text).ok(),
+            "tinyint" | "smallint" | "mediumint" | "int" | "integer" | "bigint" | "year" => text
+                .parse::<i64>()
+                .map(Json::from)
+                .or_else(|_| text.parse::<u64>().map(Json::from))
+                .ok(),
+            "float" | "double" | "real" => text.parse::<f64>().ok().and_then(serde_json::Number::from_f64).map(Json::Number),
+            _ => None,
+        };
+        parsed.unwrap_or_else(|| Json::String(text.to_string()))
+    }
 }
 
 #[async_trait]
@@ -154,58 +986,391 @@ impl DatabaseDriver for MysqlDriver {
         Ok(filtered)
     }
 
-    async fn dump_database(&self, db_name: &str, writer: Box<dyn Write + Send>) -> Result<()> {
-        self.dump_database_silent(db_name, writer, false).await
+    async fn dump_database(&self, db_name: &str, writer: Box<dyn Write + Send>) -> Result<DumpReport> {
+        self.dump_database_silent(db_name, writer, Verbosity::Normal, None).await
+    }
+
+    async fn dump_database_silent(
+        &self,
+        db_name: &str,
+        writer: Box<dyn Write + Send>,
+        verbosity: Verbosity,
+        progress: Option<ProgressCallback>,
+    ) -> Result<DumpReport> {
+        self.dump_database_resumable(db_name, writer, verbosity, progress, None, None).await
     }
 
-    async fn dump_database_silent(&self, db_name: &str, mut writer: Box<dyn Write + Send>, silent: bool) -> Result<()> {
-        if !silent {
+    async fn dump_database_resumable(
+        &self,
+        db_name: &str,
+        writer: Box<dyn Write + Send>,
+        verbosity: Verbosity,
+        progress: Option<ProgressCallback>,
+        resume: Option<DumpCheckpoint>,
+        on_checkpoint: Option<CheckpointCallback>,
+    ) -> Result<DumpReport> {
+        if !verbosity.is_quiet() {
             info!("Starting dump of database: {}", db_name);
         }
-        let mut conn = self.get_conn().await?;
-        let header = format!(
-            "-- MySQL dump generated by tlm-sql-backup\n\
-             -- Database: {}\n\
-             -- Generated at: {}\n\n\
-             SET FOREIGN_KEY_CHECKS=0;\n\
-             SET SQL_MODE='NO_AUTO_VALUE_ON_ZERO';\n\n",
-            db_name,
-            chrono::Utc::now().format("%Y-%m-%d %H:%M:%S UTC")
-        );
-        writer.write_all(header.as_bytes())?;
+        let mut report = DumpReport::default();
+        let mut writer = CountingWriter {
+            inner: writer,
+            written: resume.as_ref().map_or(0, |c| c.offset),
+        };
+        let mut conn = self.get_dump_conn().await?;
+        if let Some(statement) = Self::session_timeouts(&self.config) {
+            conn.query_drop(statement).await?;
+        }
+        for statement in Self::session_settings(&self.config) {
+            conn.query_drop(statement).await?;
+        }
+        match &resume {
+            Some(checkpoint) => report.warnings.push(format!(
+                "{}: dump resumed after {} table(s); tables before and after the retry were read at different times",
+                db_name,
+                checkpoint.tables.len()
+            )),
+            None if self.dump_format == DumpFormat::Ndjson => {}
+            None => {
+                let header = format!(
+                    "-- MySQL dump generated by tlm-sql-backup\n\
+                     -- Database: {}\n\
+                     -- Generated at: {}\n\n\
+                     {}",
+                    db_name,
+                    chrono::Utc::now().format("%Y-%m-%d %H:%M:%S UTC"),
+                    Self::dump_preamble(&self.config)
+                );
+                writer.write_all(header.as_bytes())?;
+            }
+        }
+
         let tables = self.get_tables(&mut conn, db_name).await?;
-        if !silent {
+        if !verbosity.is_quiet() {
             info!("Found {} tables in database {}", tables.len(), db_name);
         }
+        if tables.is_empty() && self.config.empty_database == EmptyDatabasePolicy::Warn {
+            let message = format!("{}: database has no tables, the dump is empty", db_name);
+            warn!("{}", message);
+            report.warnings.push(message);
+        }
+
+        self.check_column_types(&mut conn, db_name, &tables, &mut report.warnings).await?;
 
-        for table in &tables {
-            if !silent {
-                debug!("Dumping table: {}", table);
+        // Locks live on this connection, so everything up to UNLOCK TABLES must use it.
+        let locked = self.lock_tables(&mut conn, db_name, &tables).await?;
+        let result = self
+            .dump_tables(
+                &mut conn,
+                db_name,
+                &tables,
+                &mut writer,
+                &mut report,
+                verbosity,
+                progress,
+                resume.unwrap_or_default(),
+                on_checkpoint,
+            )
+            .await;
+        if locked {
+            if let Err(e) = conn.query_drop("UNLOCK TABLES").await {
+                warn!("Failed to release table locks on {}: {}", self.config.name, e);
             }
-            let table_header = format!("\n-- Table: {}\n-- ----------------------------------------\n\n", table);
-            writer.write_all(table_header.as_bytes())?;
-            let drop_stmt = format!("DROP TABLE IF EXISTS `{}`;\n\n", table);
-            writer.write_all(drop_stmt.as_bytes())?;
-            let create_stmt = self.get_create_table(&mut conn, db_name, table).await?;
-            writer.write_all(create_stmt.as_bytes())?;
-            writer.write_all(b";\n\n")?;
-            self.dump_table_data(&mut conn, db_name, table, &mut writer).await?;
         }
-        let footer = "\nSET FOREIGN_KEY_CHECKS=1;\n";
-        writer.write_all(footer.as_bytes())?;
+        result?;
 
-        if !silent {
+        if !verbosity.is_quiet() {
             info!("Completed dump of database: {}", db_name);
         }
+        Ok(report)
+    }
+
+    async fn change_marker(&self, db_name: &str) -> Result<Option<String>> {
+        // UPDATE_TIME is NULL for some engines (and for InnoDB before 5.7 or after
+        // a server restart), in which case the database is treated as changed.
+        let mut conn = self.get_conn().await?;
+        let row: Option<(i64, Option<String>)> = conn
+            .exec_first(
+                "SELECT COUNT(*), CAST(MAX(UPDATE_TIME) AS CHAR) FROM INFORMATION_SCHEMA.TABLES WHERE TABLE_SCHEMA = ?",
+                (db_name,),
+            )
+            .await?;
+
+        Ok(match row {
+            Some((count, Some(updated))) => Some(format!("{}@{}", count, updated)),
+            _ => None,
+        })
+    }
+
+    async fn restore_database(&self, db_name: &str, mut reader: Box<dyn BufRead + Send>) -> Result<()> {
+        info!("Restoring database: {}", db_name);
+        let mut conn = self.get_conn().await?;
+        conn.query_drop(format!("CREATE DATABASE IF NOT EXISTS `{}`", db_name)).await?;
+        conn.query_drop(format!("USE `{}`", db_name)).await?;
+
+        // Dumps never contain raw newlines inside values, so a line ending in `;`
+        // always terminates a statement.
+        let mut statement = String::new();
+        let mut line = String::new();
+        let mut executed = 0usize;
+        loop {
+            line.clear();
+            if reader.read_line(&mut line)? == 0 {
+                break;
+            }
+            let trimmed = line.trim_end();
+            if statement.is_empty() && (trimmed.is_empty() || trimmed.starts_with("--")) {
+                continue;
+            }
+            statement.push_str(&line);
+            if trimmed.ends_with(';') {
+                conn.query_drop(statement.as_str()).await?;
+                statement.clear();
+                executed += 1;
+            }
+        }
+        if !statement.trim().is_empty() {
+            conn.query_drop(statement.as_str()).await?;
+            executed += 1;
+        }
+
+        info!("Restored database {} ({} statements)", db_name, executed);
+        Ok(())
+    }
+
+    async fn drop_database(&self, db_name: &str) -> Result<()> {
+        let mut conn = self.get_conn().await?;
+        conn.query_drop(format!("DROP DATABASE IF EXISTS `{}`", db_name)).await?;
+        Ok(())
+    }
+
+    async fn row_counts(&self, db_name: &str) -> Result<BTreeMap<String, u64>> {
+        let mut conn = self.get_conn().await?;
+        let mut counts = BTreeMap::new();
+        for table in self.get_tables(&mut conn, db_name).await? {
+            let count: Option<u64> = conn
+                .query_first(format!("SELECT COUNT(*) FROM `{}`.`{}`", db_name, table))
+                .await?;
+            counts.insert(table, count.unwrap_or(0));
+        }
+        Ok(counts)
+    }
+
+    async fn close(&self) -> Result<()> {
+        if self.shared.is_some() {
+            return Ok(());
+        }
+        self.pool.clone().disconnect().await?;
+        if let Some(pool) = &self.dump_pool {
+            pool.clone().disconnect().await?;
+        }
         Ok(())
     }
 
+    fn set_pk_ranges(&mut self, ranges: Vec<PkRange>) {
+        self.pk_ranges = ranges;
+    }
+
+    fn set_dump_format(&mut self, format: DumpFormat) {
+        self.dump_format = format;
+    }
+
     fn engine_name(&self) -> &'static str {
         "MySQL"
     }
-}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-impl Drop for MysqlDriver {
-    fn drop(&mut self) {
+    fn unreachable_config(name: &str) -> DatabaseConfig {
+        DatabaseConfig {
+            name: name.to_string(),
+            host: "127.0.0.1".to_string(),
+            port: 1,
+            ..DatabaseConfig::default()
+        }
     }
-}
+
+    #[tokio::test]
+    async fn test_shared_pools_are_reused_per_connection() {
+        let config = unreachable_config("shared-reuse");
+        let first = MysqlDriver::shared(&config).unwrap();
+        let second = MysqlDriver::shared(&config).unwrap();
+        assert!(first.shared.is_some());
+        assert_eq!(first.shared, second.shared);
+        assert_eq!(MysqlDriver::new(&config).unwrap().shared, None);
+    }
+
+    #[tokio::test]
+    async fn test_shared_pools_replaced_when_settings_change() {
+        let config = unreachable_config("shared-replace");
+        let before = MysqlDriver::shared(&config).unwrap();
+        let changed = DatabaseConfig {
+            username: "backup".to_string(),
+            ..config.clone()
+        };
+        let after = MysqlDriver::shared(&changed).unwrap();
+        assert_ne!(before.shared, after.shared);
+        assert_eq!(MysqlDriver::shared(&changed).unwrap().shared, after.shared);
+    }
+
+    #[tokio::test]
+    async fn test_close_leaves_shared_pools_open() {
+        let config = unreachable_config("shared-close");
+        let driver = MysqlDriver::shared(&config).unwrap();
+        driver.close().await.unwrap();
+
+        // A disconnected pool fails with PoolDisconnected; an open one tries the server.
+        let reused = MysqlDriver::shared(&config).unwrap();
+        assert_eq!(reused.shared, driver.shared);
+        match reused.get_conn().await {
+            Err(BackupError::Database(e)) => assert!(!e.contains("disconnected"), "{}", e),
+            other => panic!("unexpected result: {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn test_session_timeouts() {
+        let config = DatabaseConfig {
+            wait_timeout_secs: 5,
+            net_write_timeout_secs: 0,
+            ..DatabaseConfig::default()
+        };
+        assert_eq!(
+            MysqlDriver::session_timeouts(&config).as_deref(),
+            Some("SET SESSION wait_timeout = 5")
+        );
+
+        let config = DatabaseConfig {
+            wait_timeout_secs: 0,
+            ..config
+        };
+        assert_eq!(MysqlDriver::session_timeouts(&config), None);
+    }
+
+    #[test]
+    fn test_dump_preamble() {
+        let config = DatabaseConfig::default();
+        assert_eq!(
+            MysqlDriver::dump_preamble(&config),
+            "SET FOREIGN_KEY_CHECKS=0;\nSET SQL_MODE='NO_AUTO_VALUE_ON_ZERO';\n\n"
+        );
+        assert_eq!(MysqlDriver::dump_postamble(&config), "\nSET FOREIGN_KEY_CHECKS=1;\n");
+
+        let config = DatabaseConfig {
+            set_names: Some("utf8mb4".to_string()),
+            set_time_zone: Some("+00:00".to_string()),
+            disable_unique_checks: true,
+            ..DatabaseConfig::default()
+        };
+        assert_eq!(
+            MysqlDriver::dump_preamble(&config),
+            "SET NAMES 'utf8mb4';\nSET TIME_ZONE='+00:00';\nSET FOREIGN_KEY_CHECKS=0;\n\
+             SET UNIQUE_CHECKS=0;\nSET SQL_MODE='NO_AUTO_VALUE_ON_ZERO';\n\n"
+        );
+        assert!(MysqlDriver::dump_postamble(&config).ends_with("SET UNIQUE_CHECKS=1;\n"));
+    }
+
+    #[test]
+    fn test_range_condition() {
+        let range = PkRange {
+            table: "shop.orders".to_string(),
+            column: None,
+            min: 100,
+            max: 200,
+        };
+        assert!(range.applies_to("shop", "orders"));
+        assert!(!range.applies_to("archive", "orders"));
+        assert_eq!(MysqlDriver::range_condition("id", &range), "`id` BETWEEN 100 AND 200");
+    }
+
+    #[test]
+    fn test_throttle_delay() {
+        let delay = MysqlDriver::throttle_delay(100, 50, Duration::from_millis(200));
+        assert_eq!(delay, Some(Duration::from_millis(300)));
+        assert_eq!(MysqlDriver::throttle_delay(100, 50, Duration::from_secs(1)), None);
+        assert_eq!(MysqlDriver::throttle_delay(0, 50, Duration::ZERO), None);
+    }
+
+    #[test]
+    fn test_strip_tablespace() {
+        let create = "CREATE TABLE `t` (\n  `id` int NOT NULL\n) /*!50100 TABLESPACE `ts1` */ ENGINE=InnoDB";
+        assert_eq!(
+            MysqlDriver::strip_tablespace(create),
+            "CREATE TABLE `t` (\n  `id` int NOT NULL\n) ENGINE=InnoDB"
+        );
+    }
+
+    #[test]
+    fn test_is_binary_type() {
+        assert!(MysqlDriver::is_binary_type("LONGBLOB"));
+        assert!(MysqlDriver::is_binary_type("varbinary"));
+        assert!(!MysqlDriver::is_binary_type("text"));
+        assert!(!MysqlDriver::is_binary_type("json"));
+    }
+
+    #[test]
+    fn test_json_literal_round_trip() {
+        // CREATE TABLE docs (body JSON) holding a nested document with quotes and backslashes.
+        let json = r#"{"meta": {"path": "C:\\data\\dump", "quote": "say \"hi\"", "tags": ["it's", "a\nb"]}}"#;
+        let literal = MysqlDriver::json_literal(json);
+        assert_eq!(
+            literal,
+            r#"'{"meta": {"path": "C:\\\\data\\\\dump", "quote": "say \\"hi\\"", "tags": ["it\'s", "a\\nb"]}}'"#
+        );
+
+        // Undo MySQL string-literal escaping the way the server does on restore.
+        let mut restored = String::new();
+        let mut chars = literal[1..literal.len() - 1].chars();
+        while let Some(c) = chars.next() {
+            if c == '\\' {
+                match chars.next() {
+                    Some('n') => restored.push('\n'),
+                    Some('r') => restored.push('\r'),
+                    Some('0') => restored.push('\0'),
+                    Some(other) => restored.push(other),
+                    None => {}
+                }
+            } else {
+                restored.push(c);
+            }
+        }
+        assert_eq!(restored, json);
+    }
+
+    #[test]
+    fn test_ndjson_value() {
+        use mysql_async::Value;
+        use serde_json::json;
+
+        let text = |s: &str| Value::Bytes(s.as_bytes().to_vec());
+        assert_eq!(MysqlDriver::ndjson_value(&text("42"), "int"), json!(42));
+        assert_eq!(MysqlDriver::ndjson_value(&text("18446744073709551615"), "bigint"), json!(u64::MAX));
+        assert_eq!(MysqlDriver::ndjson_value(&text("1.5"), "double"), json!(1.5));
+        assert_eq!(MysqlDriver::ndjson_value(&text("10.10"), "decimal"), json!("10.10"));
+        assert_eq!(MysqlDriver::ndjson_value(&text(r#"{"a": [1]}"#), "json"), json!({"a": [1]}));
+        assert_eq!(MysqlDriver::ndjson_value(&text("2024-01-01"), "date"), json!("2024-01-01"));
+        assert_eq!(MysqlDriver::ndjson_value(&Value::Bytes(vec![0xff, 0x00]), "varchar"), json!("ff00"));
+        assert_eq!(MysqlDriver::ndjson_value(&text("ab"), "blob"), json!("6162"));
+        assert_eq!(MysqlDriver::ndjson_value(&Value::NULL, "int"), json!(null));
+    }
+
+    #[test]
+    fn test_write_ndjson_large_matches_ndjson_value() {
+        let cases: [(&[u8], &str); 4] = [
+            (b"line\n\"quoted\" \xc3\xa9", "longtext"),
+            (br#"{"a": [1, "x"]}"#, "json"),
+            (b"\x00\x01\xfe", "longblob"),
+            (b"\xff\x00", "mediumtext"),
+        ];
+        for (bytes, data_type) in cases {
+            let mut out = Vec::new();
+            MysqlDriver::write_ndjson_large(&mut out, bytes, data_type).unwrap();
+            let written: serde_json::Value = serde_json::from_slice(&out).unwrap();
+            let expected = MysqlDriver::ndjson_value(&mysql_async::Value::Bytes(bytes.to_vec()), data_type);
+            assert_eq!(written, expected, "{}", data_type);
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use crate::config::{expand_home, DatabaseConfig};
+use crate::error::{BackupError, Result};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use tracing::debug;
+
+pub const DEFAULT_OPTION_GROUP: &str = "client";
+
+#[derive(Debug, Default)]
+pub struct OptionFile {
+    groups: HashMap<String, HashMap<String, String>>,
+}
+
+impl OptionFile {
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path).map_err(|e| {
+            BackupError::Config(format!("Failed to read option file {}: {}", path.display(), e))
+        })?;
+        Ok(Self::parse(&contents))
+    }
+
+    pub fn parse(contents: &str) -> Self {
+        let mut groups: HashMap<String, HashMap<String, String>> = HashMap::new();
+        let mut current: Option<String> = None;
+
+        for line in contents.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with(';') || line.starts_with('!') {
+                continue;
+            }
+
+            if line.starts_with('[') && line.ends_with(']') {
+                let group = line[1..line.len() - 1].trim().to_lowercase();
+                groups.entry(group.clone()).or_default();
+                current = Some(group);
+                continue;
+            }
+
+            let Some(group) = &current else {
+                continue;
+            };
+
+            let (key, value) = match line.split_once('=') {
+                Some((k, v)) => (k.trim(), unquote(v.trim())),
+                None => (line, String::new()),
+            };
+            groups
+                .entry(group.clone())
+                .or_default()
+                .insert(normalize_key(key), value);
+        }
+
+        Self { groups }
+    }
+
+    pub fn get(&self, group: &str, key: &str) -> Option<&str> {
+        let key = normalize_key(key);
+        self.groups
+            .get(&group.to_lowercase())
+            .and_then(|g| g.get(&key))
+            .or_else(|| self.groups.get(DEFAULT_OPTION_GROUP).and_then(|g| g.get(&key)))
+            .map(|v| v.as_str())
+    }
+}
+
+fn normalize_key(key: &str) -> String {
+    key.trim().to_lowercase().replace('-', "_")
+}
+
+fn unquote(value: &str) -> String {
+    let quoted = value.len() >= 2
+        && ((value.starts_with('"') && value.ends_with('"'))
+            || (value.starts_with('\'') && value.ends_with('\'')));
+    if quoted {
+        value[1..value.len() - 1].to_string()
+    } else {
+        value.split(" #").next().unwrap_or(value).trim().to_string()
+    }
+}
+
+pub fn resolve_credentials(config: &DatabaseConfig) -> Result<DatabaseConfig> {
+    let Some(option_file) = &config.option_file else {
+        return Ok(config.clone());
+    };
+
+    let path = expand_home(option_file);
+    let group = config.option_group.as_deref().unwrap_or(DEFAULT_OPTION_GROUP);
+    debug!("Reading MySQL credentials from {} [{}]", path.display(), group);
+    let options = OptionFile::load(&path)?;
+
+    let mut resolved = config.clone();
+    if let Some(host) = options.get(group, "host") {
+        resolved.host = host.to_string();
+    }
+    if let Some(port) = options.get(group, "port") {
+        resolved.port = port.parse().map_err(|_| {
+            BackupError::Config(format!("Invalid port '{}' in option file {}", port, path.display()))
+        })?;
+    }
+    if let Some(user) = options.get(group, "user") {
+        resolved.username = user.to_string();
+    }
+    if let Some(password) = options.get(group, "password") {
+        resolved.password = password.to_string();
+    }
+
+    Ok(resolved)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_option_file() {
+        let options = OptionFile::parse(
+            "# MySQL client options\n\
+             [client]\n\
+             user = backup\n\
+             password = \"s3cr#t\"\n\
+             host=db.internal\n\
+             \n\
+             [mysqldump]\n\
+             user=dumper\n\
+             default-character-set = utf8mb4 # trailing comment\n",
+        );
+
+        assert_eq!(options.get("client", "user"), Some("backup"));
+        assert_eq!(options.get("client", "password"), Some("s3cr#t"));
+        assert_eq!(options.get("mysqldump", "user"), Some("dumper"));
+        assert_eq!(options.get("mysqldump", "host"), Some("db.internal"));
+        assert_eq!(options.get("mysqldump", "default_character_set"), Some("utf8mb4"));
+        assert_eq!(options.get("client", "port"), None);
+    }
+}
@@ -0,0 +1,85 @@
+/// Matches `name` against a shell-style pattern where `*` matches any run of
+/// characters and `?` matches exactly one.
+pub fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star_p, star_n)) => {
+                    p = star_p + 1;
+                    n = star_n + 1;
+                    backtrack = Some((star_p, star_n + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+pub fn is_pattern(s: &str) -> bool {
+    s.contains(['*', '?'])
+}
+
+/// Expands the patterns in `requested` against the databases on the server and
+/// drops anything matching `exclude`. Plain names are kept as-is.
+pub fn resolve_databases(requested: &[String], available: &[String], exclude: &[String]) -> Vec<String> {
+    let mut resolved: Vec<String> = Vec::new();
+    for entry in requested {
+        let matches: Vec<&String> = if is_pattern(entry) {
+            available.iter().filter(|db| glob_match(entry, db)).collect()
+        } else {
+            vec![entry]
+        };
+        for db in matches {
+            if !resolved.contains(db) {
+                resolved.push(db.clone());
+            }
+        }
+    }
+
+    resolved.retain(|db| !exclude.iter().any(|pattern| glob_match(pattern, db)));
+    resolved
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_databases() {
+        assert!(glob_match("*_test", "shop_test"));
+        assert!(glob_match("scratch_*", "scratch_"));
+        assert!(glob_match("db?", "db1"));
+        assert!(!glob_match("db?", "db12"));
+        assert!(!glob_match("*_test", "test_shop"));
+
+        let available: Vec<String> = ["shop", "shop_test", "scratch_1", "crm"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let exclude = vec!["*_test".to_string(), "scratch_*".to_string()];
+
+        assert_eq!(
+            resolve_databases(&["*".to_string()], &available, &exclude),
+            vec!["shop", "crm"]
+        );
+        assert_eq!(
+            resolve_databases(&["crm".to_string(), "s*".to_string()], &available, &[]),
+            vec!["crm", "shop", "shop_test", "scratch_1"]
+        );
+    }
+}
@@ -1,26 +1,80 @@
 use std::fmt;
 use std::io;
+/// What went wrong talking to the database, so callers can react (retry, hint) without parsing messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DatabaseErrorKind {
+    ConnectionRefused,
+    AuthFailed,
+    /// The server asked for an authentication plugin the client can't or won't use.
+    AuthPlugin,
+    Timeout,
+    Other,
+}
+
+impl DatabaseErrorKind {
+    /// Errors worth retrying: the server may be restarting or briefly overloaded.
+    pub fn is_transient(&self) -> bool {
+        matches!(self, DatabaseErrorKind::ConnectionRefused | DatabaseErrorKind::Timeout)
+    }
+
+    fn hint(&self) -> Option<&'static str> {
+        match self {
+            DatabaseErrorKind::ConnectionRefused => Some("check that the server is running and the host and port are correct"),
+            DatabaseErrorKind::AuthFailed => Some("check the username and password"),
+            DatabaseErrorKind::AuthPlugin => Some(
+                "the account uses an authentication plugin that is not enabled; \
+                 set enable_cleartext_plugin = true for LDAP/PAM accounts (only over TLS or a trusted network), \
+                 or secure_auth = false for accounts still on mysql_old_password",
+            ),
+            DatabaseErrorKind::Timeout => Some("the server is slow to respond or unreachable"),
+            DatabaseErrorKind::Other => None,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum BackupError {
     Config(String),
-    Database(String),
+    Database(DatabaseErrorKind, String),
     Compression(String),
     Upload(String),
     Io(io::Error),
     Serialization(String),
+    History(String),
 }
 
 impl fmt::Display for BackupError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             BackupError::Config(msg) => write!(f, "Configuration error: {}", msg),
-            BackupError::Database(msg) => write!(f, "Database error: {}", msg),
+            BackupError::Database(kind, msg) => match kind.hint() {
+                Some(hint) => write!(f, "Database error: {} ({})", msg, hint),
+                None => write!(f, "Database error: {}", msg),
+            },
             BackupError::Compression(msg) => write!(f, "Compression error: {}", msg),
             BackupError::Upload(msg) => write!(f, "Upload error: {}", msg),
             BackupError::Io(err) => write!(f, "IO error: {}", err),
             BackupError::Serialization(msg) => write!(f, "Serialization error: {}", msg),
+            BackupError::History(msg) => write!(f, "History store error: {}", msg),
+        }
+    }
+}
+
+impl BackupError {
+    pub fn database(message: impl Into<String>) -> Self {
+        BackupError::Database(DatabaseErrorKind::Other, message.into())
+    }
+
+    pub fn database_kind(&self) -> Option<DatabaseErrorKind> {
+        match self {
+            BackupError::Database(kind, _) => Some(*kind),
+            _ => None,
         }
     }
+
+    pub fn is_transient(&self) -> bool {
+        self.database_kind().is_some_and(|kind| kind.is_transient())
+    }
 }
 
 impl std::error::Error for BackupError {
@@ -50,9 +104,37 @@ impl From<toml::ser::Error> for BackupError {
     }
 }
 
+impl From<serde_json::Error> for BackupError {
+    fn from(err: serde_json::Error) -> Self {
+        BackupError::Serialization(err.to_string())
+    }
+}
+
 impl From<mysql_async::Error> for BackupError {
     fn from(err: mysql_async::Error) -> Self {
-        BackupError::Database(err.to_string())
+        let kind = match &err {
+            mysql_async::Error::Io(mysql_async::IoError::Io(io_err)) => match io_err.kind() {
+                io::ErrorKind::ConnectionRefused => DatabaseErrorKind::ConnectionRefused,
+                io::ErrorKind::TimedOut => DatabaseErrorKind::Timeout,
+                _ => DatabaseErrorKind::Other,
+            },
+            mysql_async::Error::Server(server_err) => match server_err.code {
+                // ER_DBACCESS_DENIED_ERROR, ER_ACCESS_DENIED_ERROR, ER_ACCESS_DENIED_NO_PASSWORD_ERROR
+                1044 | 1045 | 1698 => DatabaseErrorKind::AuthFailed,
+                // ER_NOT_SUPPORTED_AUTH_MODE
+                1251 => DatabaseErrorKind::AuthPlugin,
+                // ER_LOCK_WAIT_TIMEOUT, ER_QUERY_TIMEOUT
+                1205 | 3024 => DatabaseErrorKind::Timeout,
+                _ => DatabaseErrorKind::Other,
+            },
+            mysql_async::Error::Driver(
+                mysql_async::DriverError::CleartextPluginDisabled
+                | mysql_async::DriverError::UnknownAuthPlugin { .. }
+                | mysql_async::DriverError::MysqlOldPasswordDisabled,
+            ) => DatabaseErrorKind::AuthPlugin,
+            _ => DatabaseErrorKind::Other,
+        };
+        BackupError::Database(kind, err.to_string())
     }
 }
 
@@ -62,6 +144,12 @@ impl From<reqwest::Error> for BackupError {
     }
 }
 
+impl From<rusqlite::Error> for BackupError {
+    fn from(err: rusqlite::Error) -> Self {
+        BackupError::History(err.to_string())
+    }
+}
+
 impl From<zip::result::ZipError> for BackupError {
     fn from(err: zip::result::ZipError) -> Self {
         BackupError::Compression(err.to_string())
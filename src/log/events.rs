@@ -0,0 +1,80 @@
+use crate::config;
+use crate::upload::UploadStatus;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use tracing::warn;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    SchedulerStarted,
+    SchedulerStopped,
+    BackupStarted {
+        connection: String,
+        databases: Vec<String>,
+    },
+    BackupCompleted {
+        connection: String,
+        databases: Vec<String>,
+        file: Option<PathBuf>,
+        size: u64,
+        duration_secs: u64,
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        uploads: Vec<UploadStatus>,
+    },
+    BackupFailed {
+        connection: String,
+        error: String,
+        duration_secs: u64,
+    },
+    UploadSucceeded {
+        uploader: String,
+        file: String,
+    },
+    UploadFailed {
+        uploader: String,
+        file: String,
+        error: String,
+    },
+}
+
+#[derive(Serialize)]
+struct EventRecord<'a> {
+    timestamp: DateTime<Utc>,
+    #[serde(flatten)]
+    event: &'a Event,
+}
+
+pub fn events_path() -> PathBuf {
+    config::config_dir().join("events.jsonl")
+}
+
+pub fn emit(event: Event) {
+    let record = EventRecord {
+        timestamp: Utc::now(),
+        event: &event,
+    };
+    let line = match serde_json::to_string(&record) {
+        Ok(line) => line,
+        Err(e) => {
+            warn!("Failed to serialize event: {}", e);
+            return;
+        }
+    };
+
+    let path = events_path();
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    let result = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| file.write_all(format!("{}\n", line).as_bytes()));
+    if let Err(e) = result {
+        warn!("Failed to write event to {:?}: {}", path, e);
+    }
+}
@@ -1,4 +1,45 @@
+pub mod events;
+
+use std::sync::atomic::{AtomicU8, Ordering};
 use tracing_subscriber::{fmt, EnvFilter};
+
+/// How much backup progress is reported: nothing, databases and uploads, or every table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Verbosity {
+    Quiet = 0,
+    Normal = 1,
+    Debug = 2,
+}
+
+impl Verbosity {
+    pub fn from_level(level: u8) -> Self {
+        match level {
+            0 => Verbosity::Quiet,
+            1 => Verbosity::Normal,
+            _ => Verbosity::Debug,
+        }
+    }
+
+    pub fn is_quiet(self) -> bool {
+        self == Verbosity::Quiet
+    }
+
+    pub fn is_debug(self) -> bool {
+        self == Verbosity::Debug
+    }
+}
+
+static VERBOSITY: AtomicU8 = AtomicU8::new(Verbosity::Normal as u8);
+
+pub fn set_verbosity(verbosity: Verbosity) {
+    VERBOSITY.store(verbosity as u8, Ordering::Relaxed);
+}
+
+/// Verbosity chosen on the command line, used for runs started from the CLI.
+pub fn verbosity() -> Verbosity {
+    Verbosity::from_level(VERBOSITY.load(Ordering::Relaxed))
+}
+
 pub fn init() {
     let filter = EnvFilter::try_from_default_env()
         .unwrap_or_else(|_| EnvFilter::new("info"));
@@ -9,5 +50,6 @@ pub fn init() {
         .with_thread_ids(false)
         .with_file(false)
         .with_line_number(false)
+        .with_writer(std::io::stderr)
         .init();
 }
@@ -7,6 +7,7 @@ mod log;
 mod upload;
 mod web;
 
+use clap::Parser;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use tracing::info;
@@ -14,7 +15,27 @@ use web::AppState;
 
 #[tokio::main]
 async fn main() {
+    let args = cli::Args::parse();
     log::init();
+    cli::output::set_quiet(args.quiet);
+    log::set_verbosity(if args.quiet {
+        log::Verbosity::Quiet
+    } else {
+        log::Verbosity::from_level(args.verbose.saturating_add(1))
+    });
+
+    if let Err(e) = config::set_active_profile(args.profile.clone()) {
+        eprintln!("Error: {}", e);
+        std::process::exit(2);
+    }
+    if let Err(e) = config::set_config_source(args.config.clone()) {
+        eprintln!("Error: {}", e);
+        std::process::exit(2);
+    }
+    if args.command.is_none() && config::reads_stdin() {
+        eprintln!("Error: --config - needs a command such as run or serve; the menu can't read from stdin");
+        std::process::exit(2);
+    }
 
     info!("TLM Database Backup CLI starting...");
 
@@ -25,7 +46,7 @@ async fn main() {
         let count = ctrl_c_count_clone.fetch_add(1, Ordering::SeqCst);
         
         if count == 0 {
-            println!("\n\nShutdown signal received. Press Ctrl+C again to force exit...");
+            println!("\n\nShutdown signal received. Finishing running backups, press Ctrl+C again to force exit...");
         } else {
             println!("\nForce exiting...");
             std::process::exit(130);
@@ -34,6 +55,91 @@ async fn main() {
     .expect("Error setting Ctrl-C handler");
 
     let app_state = AppState::new(String::new(), String::new());
+    tokio::spawn(backup::shutdown::drain_on_signal(ctrl_c_count.clone(), app_state.clone()));
+
+    match args.command {
+        Some(cli::Command::Run { connection, databases }) => {
+            match cli::run_once(app_state, connection.as_deref(), &databases).await {
+                Ok(true) => return,
+                Ok(false) => std::process::exit(1),
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        Some(cli::Command::Serve { once: true }) => {
+            match cli::serve::run_once(ctrl_c_count, app_state).await {
+                Ok(true) => return,
+                Ok(false) => std::process::exit(1),
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        Some(cli::Command::Serve { once: false }) => {
+            if let Err(e) = cli::serve::run_service(ctrl_c_count, app_state).await {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+            return;
+        }
+        Some(cli::Command::Backup { connection, database }) => {
+            match cli::run_single_database(app_state, &connection, &database).await {
+                Ok(true) => return,
+                Ok(false) => std::process::exit(1),
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        Some(cli::Command::Benchmark { connection, database }) => {
+            if let Err(e) = cli::benchmark::run_benchmark(&connection, &database).await {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+            return;
+        }
+        Some(cli::Command::TestRestore { archive, compare_rows }) => {
+            match cli::test_restore::run_test_restore(archive, compare_rows).await {
+                Ok(true) => return,
+                Ok(false) => std::process::exit(1),
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        Some(cli::Command::Dump { connection, database }) => {
+            if let Err(e) = cli::dump::dump_to_stdout(&connection, &database).await {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+            return;
+        }
+        Some(cli::Command::Config {
+            command: cli::ConfigCommand::AddConnection { name, url, option_file, option_group, force },
+        }) => {
+            if let Err(e) = cli::connections::add_connection(&name, url.as_deref(), option_file, option_group, force) {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+            return;
+        }
+        Some(cli::Command::Join { manifest, output }) => {
+            match backup::compression::join_archive(&manifest, output.as_deref()) {
+                Ok(path) => println!("{}", path.display()),
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
+            }
+            return;
+        }
+        None => {}
+    }
 
     match cli::run_menu(ctrl_c_count, app_state).await {
         Ok(_) => {
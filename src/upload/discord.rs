@@ -1,9 +1,12 @@
+use super::http::build_client;
 use super::uploader::{BackupMetadata, BackupUploader};
-use crate::config::DiscordConfig;
+use crate::config::{format_local, normalize_channel_name, DiscordConfig, HttpConfig};
 use crate::error::{BackupError, Result};
+use crate::log::Verbosity;
 use async_trait::async_trait;
 use reqwest::multipart::{Form, Part};
 use reqwest::Client;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use std::path::Path;
 use tokio::fs::File;
@@ -12,6 +15,9 @@ use tracing::{debug, error, info, warn};
 
 const DISCORD_API_BASE: &str = "https://discord.com/api/v10";
 const MAX_FILE_SIZE: u64 = 8 * 1024 * 1024;
+const SUPPRESS_NOTIFICATIONS: u64 = 1 << 12;
+const MAX_THREAD_NAME_CHARS: usize = 100;
+const DEFAULT_THREAD_TITLE: &str = "Backup {connection} - {timestamp}";
 
 pub struct DiscordUploader {
     config: DiscordConfig,
@@ -48,6 +54,8 @@ struct CreateForumPost {
 #[derive(Debug, Serialize)]
 struct CreateMessage {
     content: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    flags: Option<u64>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -55,18 +63,38 @@ struct CreatedThread {
     id: String,
 }
 
-impl DiscordUploader {
+#[derive(Debug, Deserialize)]
+struct ThreadList {
+    threads: Vec<Thread>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Thread {
+    id: String,
+    name: String,
+    parent_id: Option<String>,
+}
 
-    pub fn new(config: &DiscordConfig) -> Self {
-        let client = Client::builder()
-            .user_agent("TLM-SQL-Backup/1.0")
-            .build()
-            .expect("Failed to create HTTP client");
+#[derive(Debug, Deserialize)]
+struct Message {
+    content: String,
+}
 
-        Self {
-            config: config.clone(),
+impl DiscordUploader {
+
+    pub fn new(config: &DiscordConfig, http: &HttpConfig) -> Result<Self> {
+        let client = build_client(http)?;
+        // Discord lowercases and dashes channel names, so look up and create the same form.
+        let forum_channel_name = normalize_channel_name(&config.forum_channel_name)
+            .map_err(|e| BackupError::Config(format!("Invalid Discord forum channel: {}", e)))?;
+
+        Ok(Self {
+            config: DiscordConfig {
+                forum_channel_name,
+                ..config.clone()
+            },
             client,
-        }
+        })
     }
 
     fn auth_header(&self) -> String {
@@ -161,12 +189,77 @@ impl DiscordUploader {
         Ok(channel.id)
     }
 
+    async fn get_json<T: DeserializeOwned>(&self, url: &str, action: &str) -> Result<T> {
+        let response = self.client
+            .get(url)
+            .header("Authorization", self.auth_header())
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let text = response.text().await.unwrap_or_default();
+            return Err(BackupError::Upload(format!("Failed to {}: {} - {}", action, status, text)));
+        }
+
+        Ok(response.json().await?)
+    }
+
+    /// Finds a post made for the same archive by an earlier attempt, e.g. a run that
+    /// crashed after posting but before it was recorded. Threads are matched by name
+    /// first, then by the SHA256 in their starter message.
+    async fn find_existing_post(&self, channel_id: &str, topic_name: &str, hash: &str) -> Result<Option<String>> {
+        let active: ThreadList = self
+            .get_json(
+                &format!("{}/guilds/{}/threads/active", DISCORD_API_BASE, self.config.guild_id),
+                "list active threads",
+            )
+            .await?;
+        let archived: ThreadList = self
+            .get_json(
+                &format!("{}/channels/{}/threads/archived/public?limit=50", DISCORD_API_BASE, channel_id),
+                "list archived threads",
+            )
+            .await?;
+
+        let candidates = active
+            .threads
+            .into_iter()
+            .filter(|t| t.parent_id.as_deref() == Some(channel_id))
+            .chain(archived.threads)
+            .filter(|t| t.name == topic_name);
+        for thread in candidates {
+            // A forum post's starter message shares the thread's id.
+            let starter: Message = self
+                .get_json(
+                    &format!("{}/channels/{}/messages/{}", DISCORD_API_BASE, thread.id, thread.id),
+                    "read forum post",
+                )
+                .await?;
+            if starter.content.contains(hash) {
+                return Ok(Some(thread.id));
+            }
+        }
+        Ok(None)
+    }
+
+    fn thread_title(&self, metadata: &BackupMetadata) -> String {
+        thread_title(
+            self.config.thread_title_template.as_deref().unwrap_or(DEFAULT_THREAD_TITLE),
+            metadata,
+        )
+    }
+
+    fn message_flags(&self) -> Option<u64> {
+        self.config.silent_posts.then_some(SUPPRESS_NOTIFICATIONS)
+    }
+
     async fn create_forum_post(
         &self,
         channel_id: &str,
         metadata: &BackupMetadata,
         file_path: &Path,
-        silent: bool,
+        verbosity: Verbosity,
     ) -> Result<()> {
         let url = format!("{}/channels/{}/threads", DISCORD_API_BASE, channel_id);
         
@@ -174,7 +267,7 @@ impl DiscordUploader {
         let file_size_mb = metadata.file_size as f64 / 1024.0 / 1024.0;
         let db_list = metadata.databases.join(", ");
         
-        let message_content = format!(
+        let mut message_content = format!(
             "**Database Backup Completed**\n\n\
              🔌 **Connection:** `{}`\n\
              📁 **Databases ({}):** `{}`\n\
@@ -186,17 +279,29 @@ impl DiscordUploader {
             metadata.connection_name,
             metadata.databases.len(),
             db_list,
-            metadata.timestamp.format("%Y-%m-%d %H:%M:%S UTC"),
+            format_local(&metadata.timestamp, "%Y-%m-%d %H:%M:%S %Z"),
             file_size_mb,
             metadata.duration_secs,
             hash_info
         );
+        if !metadata.tags.is_empty() {
+            message_content.push_str(&format!("\n🏷️ **Tags:** `{}`", metadata.tags.join("`, `")));
+        }
 
-        let topic_name = format!(
-            "Backup {} - {}",
-            metadata.connection_name,
-            metadata.timestamp.format("%Y-%m-%d %H:%M")
-        );
+        let topic_name = self.thread_title(metadata);
+
+        if let Some(hash) = &metadata.file_hash {
+            match self.find_existing_post(channel_id, &topic_name, hash).await {
+                Ok(Some(thread_id)) => {
+                    if !verbosity.is_quiet() {
+                        info!("Backup already posted in thread {}, skipping", thread_id);
+                    }
+                    return Ok(());
+                }
+                Ok(None) => {}
+                Err(e) => warn!("Could not check for an existing forum post, posting anyway: {}", e),
+            }
+        }
 
         if metadata.file_size > MAX_FILE_SIZE {
             warn!(
@@ -213,6 +318,7 @@ impl DiscordUploader {
                         message_content,
                         metadata.file_path
                     ),
+                    flags: self.message_flags(),
                 },
             };
 
@@ -232,7 +338,7 @@ impl DiscordUploader {
                 )));
             }
 
-            if !silent {
+            if !verbosity.is_quiet() {
                 info!("Created forum post (without attachment due to size limit)");
             }
             return Ok(());
@@ -250,7 +356,7 @@ impl DiscordUploader {
             .file_name(file_name.clone())
             .mime_str("application/zip")?;
 
-        let payload_json = serde_json::json!({
+        let mut payload_json = serde_json::json!({
             "name": topic_name,
             "message": {
                 "content": message_content,
@@ -260,6 +366,9 @@ impl DiscordUploader {
                 }]
             }
         });
+        if let Some(flags) = self.message_flags() {
+            payload_json["message"]["flags"] = flags.into();
+        }
 
         let form = Form::new()
             .text("payload_json", payload_json.to_string())
@@ -282,7 +391,7 @@ impl DiscordUploader {
         }
 
         let thread: CreatedThread = response.json().await?;
-        if !silent {
+        if !verbosity.is_quiet() {
             info!("Created forum post with attachment: thread ID {}", thread.id);
         }
         Ok(())
@@ -292,19 +401,19 @@ impl DiscordUploader {
 #[async_trait]
 impl BackupUploader for DiscordUploader {
     async fn upload(&self, metadata: &BackupMetadata, file_path: &Path) -> Result<()> {
-        self.upload_silent(metadata, file_path, false).await
+        self.upload_silent(metadata, file_path, Verbosity::Normal).await
     }
 
-    async fn upload_silent(&self, metadata: &BackupMetadata, file_path: &Path, silent: bool) -> Result<()> {
-        if !silent {
+    async fn upload_silent(&self, metadata: &BackupMetadata, file_path: &Path, verbosity: Verbosity) -> Result<()> {
+        if !verbosity.is_quiet() {
             info!("Uploading backup to Discord forum");
         }
 
         let channel_id = self.get_or_create_forum_channel().await?;
 
-        self.create_forum_post(&channel_id, metadata, file_path, silent).await?;
+        self.create_forum_post(&channel_id, metadata, file_path, verbosity).await?;
 
-        if !silent {
+        if !verbosity.is_quiet() {
             info!("Discord upload completed successfully");
         }
         Ok(())
@@ -325,3 +434,64 @@ impl BackupUploader for DiscordUploader {
         "Discord Forum"
     }
 }
+
+/// Fills a thread title template, cut to Discord's thread name limit.
+fn thread_title(template: &str, metadata: &BackupMetadata) -> String {
+    let title = template
+        .replace("{connection}", &metadata.connection_name)
+        .replace("{databases}", &metadata.databases.join(", "))
+        .replace("{database_count}", &metadata.databases.len().to_string())
+        .replace("{tags}", &metadata.tags.join(", "))
+        .replace("{timestamp}", &format_local(&metadata.timestamp, "%Y-%m-%d %H:%M"))
+        .replace("{size_mb}", &format!("{:.2}", metadata.file_size as f64 / 1024.0 / 1024.0));
+    let title = title.trim();
+    if title.chars().count() <= MAX_THREAD_NAME_CHARS {
+        return title.to_string();
+    }
+    let mut truncated: String = title.chars().take(MAX_THREAD_NAME_CHARS - 1).collect();
+    truncated.push('…');
+    truncated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use std::collections::BTreeMap;
+
+    fn metadata(databases: &[&str]) -> BackupMetadata {
+        BackupMetadata {
+            databases: databases.iter().map(|db| db.to_string()).collect(),
+            connection_name: "prod".to_string(),
+            timestamp: Utc::now(),
+            file_size: 3 * 1024 * 1024,
+            file_hash: None,
+            duration_secs: 1,
+            file_path: "backup.zip".to_string(),
+            tags: vec!["nightly".to_string()],
+            binlog_positions: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_thread_title_fills_placeholders() {
+        let title = thread_title(
+            "  {connection}: {databases} ({database_count}) [{tags}] {size_mb}MB  ",
+            &metadata(&["app", "crm"]),
+        );
+        assert_eq!(title, "prod: app, crm (2) [nightly] 3.00MB");
+    }
+
+    #[test]
+    fn test_thread_title_truncated_to_limit() {
+        let databases: Vec<String> = (0..40).map(|i| format!("db_é{}", i)).collect();
+        let databases: Vec<&str> = databases.iter().map(String::as_str).collect();
+        let title = thread_title("{databases}", &metadata(&databases));
+        assert_eq!(title.chars().count(), MAX_THREAD_NAME_CHARS);
+        assert!(title.ends_with('…'));
+        assert!(title.starts_with("db_é0, db_é1"));
+
+        let exact = "x".repeat(MAX_THREAD_NAME_CHARS);
+        assert_eq!(thread_title(&exact, &metadata(&[])), exact);
+    }
+}
@@ -0,0 +1,80 @@
+use super::BackupUploader;
+use crate::config::UploadConfig;
+use std::collections::{HashMap, HashSet};
+use std::sync::RwLock;
+use tracing::{info, warn};
+
+pub const MAX_CONSECUTIVE_FAILURES: u32 = 3;
+
+static DISABLED_UPLOADERS: RwLock<Option<HashSet<String>>> = RwLock::new(None);
+static FAILURE_COUNTS: RwLock<Option<HashMap<String, u32>>> = RwLock::new(None);
+
+pub fn is_disabled(name: &str) -> bool {
+    DISABLED_UPLOADERS
+        .read()
+        .unwrap()
+        .as_ref()
+        .is_some_and(|set| set.contains(name))
+}
+
+fn disable(name: &str) {
+    DISABLED_UPLOADERS
+        .write()
+        .unwrap()
+        .get_or_insert_with(HashSet::new)
+        .insert(name.to_string());
+}
+
+pub fn reset() {
+    *DISABLED_UPLOADERS.write().unwrap() = None;
+    *FAILURE_COUNTS.write().unwrap() = None;
+}
+
+pub fn record_success(name: &str) {
+    if let Some(counts) = FAILURE_COUNTS.write().unwrap().as_mut() {
+        counts.remove(name);
+    }
+}
+
+pub fn record_failure(config: &UploadConfig, name: &str) {
+    let failures = {
+        let mut counts = FAILURE_COUNTS.write().unwrap();
+        let count = counts
+            .get_or_insert_with(HashMap::new)
+            .entry(name.to_string())
+            .or_insert(0);
+        *count += 1;
+        *count
+    };
+    if config.disable_failing && failures >= MAX_CONSECUTIVE_FAILURES {
+        warn!(
+            "Disabling uploader {} after {} consecutive failures; fix its configuration and restart the scheduler",
+            name, failures
+        );
+        disable(name);
+    }
+}
+
+pub async fn check_uploaders(
+    config: &UploadConfig,
+    uploaders: &[Box<dyn BackupUploader>],
+) -> Vec<(&'static str, Option<String>)> {
+    let mut results = Vec::new();
+    for uploader in uploaders {
+        match uploader.test_connection().await {
+            Ok(_) => {
+                info!("Uploader {} is healthy", uploader.name());
+                record_success(uploader.name());
+                results.push((uploader.name(), None));
+            }
+            Err(e) => {
+                warn!("Uploader {} failed its health check: {}", uploader.name(), e);
+                if config.disable_failing {
+                    disable(uploader.name());
+                }
+                results.push((uploader.name(), Some(e.to_string())));
+            }
+        }
+    }
+    results
+}
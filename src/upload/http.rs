@@ -0,0 +1,66 @@
+use crate::config::HttpConfig;
+use crate::error::{BackupError, Result};
+use reqwest::{Certificate, Client, NoProxy, Proxy};
+use std::fs;
+use tracing::debug;
+
+const USER_AGENT: &str = "TLM-SQL-Backup/1.0";
+const PROXY_ENV_VARS: &[&str] = &[
+    "HTTPS_PROXY",
+    "https_proxy",
+    "HTTP_PROXY",
+    "http_proxy",
+    "ALL_PROXY",
+    "all_proxy",
+];
+
+pub fn build_client(config: &HttpConfig) -> Result<Client> {
+    let mut builder = Client::builder()
+        .user_agent(USER_AGENT)
+        .timeout(config.timeout())
+        .connect_timeout(config.connect_timeout());
+
+    if let Some(ca_path) = &config.ca_cert_path {
+        let bytes = fs::read(ca_path).map_err(|e| {
+            BackupError::Config(format!("Failed to read CA certificate {}: {}", ca_path.display(), e))
+        })?;
+        let certs = Certificate::from_pem_bundle(&bytes)
+            .or_else(|_| Certificate::from_der(&bytes).map(|c| vec![c]))
+            .map_err(|e| {
+                BackupError::Config(format!("Invalid CA certificate {}: {}", ca_path.display(), e))
+            })?;
+        debug!("Adding {} root certificate(s) from {}", certs.len(), ca_path.display());
+        for cert in certs {
+            builder = builder.add_root_certificate(cert);
+        }
+    }
+
+    // An explicit proxy replaces reqwest's system proxy lookup, so without one
+    // the standard HTTP(S)_PROXY / ALL_PROXY / NO_PROXY variables stay in effect.
+    if let Some(proxy_url) = &config.proxy {
+        let proxy = Proxy::all(proxy_url)
+            .map_err(|e| BackupError::Config(format!("Invalid proxy URL '{}': {}", proxy_url, e)))?
+            .no_proxy(config.no_proxy.as_deref().and_then(NoProxy::from_string));
+        debug!("Using HTTP proxy {}", proxy_url);
+        builder = builder.proxy(proxy);
+    } else if let Some(proxy) = proxy_summary(config) {
+        debug!("Using HTTP proxy {}", proxy);
+    }
+
+    builder
+        .build()
+        .map_err(|e| BackupError::Config(format!("Failed to create HTTP client: {}", e)))
+}
+
+pub fn proxy_summary(config: &HttpConfig) -> Option<String> {
+    if let Some(proxy_url) = &config.proxy {
+        return Some(proxy_url.clone());
+    }
+
+    PROXY_ENV_VARS.iter().find_map(|name| {
+        std::env::var(name)
+            .ok()
+            .filter(|v| !v.trim().is_empty())
+            .map(|v| format!("{} (from {})", v, name))
+    })
+}
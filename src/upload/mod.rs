@@ -1,18 +1,33 @@
 mod discord;
+pub mod health;
+mod http;
+mod pending;
 mod uploader;
 
 pub use discord::DiscordUploader;
-pub use uploader::{BackupMetadata, BackupUploader};
+pub use http::{build_client, proxy_summary};
+pub use pending::{retry_pending_uploads, PendingUploads};
+pub use uploader::{BackupMetadata, BackupUploader, UploadStatus};
 
 use crate::config::UploadConfig;
-use crate::error::Result;
+use tracing::{debug, error};
 
 pub fn create_uploaders(config: &UploadConfig) -> Vec<Box<dyn BackupUploader>> {
     let mut uploaders: Vec<Box<dyn BackupUploader>> = Vec::new();
 
     if let Some(discord_config) = &config.discord {
-        uploaders.push(Box::new(DiscordUploader::new(discord_config)));
+        match DiscordUploader::new(discord_config, &config.http) {
+            Ok(uploader) => uploaders.push(Box::new(uploader)),
+            Err(e) => error!("Failed to initialize Discord uploader: {}", e),
+        }
     }
 
+    uploaders.retain(|u| {
+        let disabled = health::is_disabled(u.name());
+        if disabled {
+            debug!("Skipping disabled uploader {}", u.name());
+        }
+        !disabled
+    });
     uploaders
 }
@@ -0,0 +1,299 @@
+use super::{create_uploaders, BackupMetadata, BackupUploader};
+use crate::config::{self, UploadConfig};
+use crate::error::Result;
+use crate::log::Verbosity;
+use fs2::FileExt;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingUpload {
+    pub uploader: String,
+    pub metadata: BackupMetadata,
+    pub attempts: u32,
+    pub last_error: String,
+}
+
+impl PendingUpload {
+    fn is_same(&self, other: &PendingUpload) -> bool {
+        self.uploader == other.uploader && self.metadata.file_path == other.metadata.file_path
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PendingUploads {
+    #[serde(default)]
+    entries: Vec<PendingUpload>,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RetrySummary {
+    pub uploaded: usize,
+    pub dropped: usize,
+    pub remaining: usize,
+}
+
+impl PendingUploads {
+    pub fn path() -> PathBuf {
+        config::config_dir().join("pending_uploads.json")
+    }
+
+    pub fn load() -> Self {
+        Self::load_from(&Self::path())
+    }
+
+    pub fn load_from(path: &Path) -> Self {
+        if !path.exists() {
+            return Self::default();
+        }
+        match fs::read_to_string(&path).map(|c| serde_json::from_str(&c)) {
+            Ok(Ok(pending)) => pending,
+            Ok(Err(e)) => {
+                warn!("Ignoring unreadable pending upload queue {:?}: {}", path, e);
+                Self::default()
+            }
+            Err(e) => {
+                warn!("Failed to read pending upload queue {:?}: {}", path, e);
+                Self::default()
+            }
+        }
+    }
+
+    /// Applies `change` to the queue as currently saved and writes it back, holding a lock
+    /// on `{path}.lock` throughout so concurrent runs and processes don't drop each
+    /// other's entries.
+    fn modify(path: &Path, change: impl FnOnce(&mut Self)) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut lock_name = path.file_name().unwrap_or_default().to_os_string();
+        lock_name.push(".lock");
+        let lock = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path.with_file_name(lock_name))?;
+        lock.lock_exclusive()?;
+
+        let mut pending = Self::load_from(path);
+        change(&mut pending);
+        fs::write(path, serde_json::to_string_pretty(&pending)?)?;
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn enqueue(uploader: &str, metadata: &BackupMetadata, error: &str) {
+        Self::enqueue_to(&Self::path(), uploader, metadata, error);
+    }
+
+    pub fn enqueue_to(path: &Path, uploader: &str, metadata: &BackupMetadata, error: &str) {
+        let entry = PendingUpload {
+            uploader: uploader.to_string(),
+            metadata: metadata.clone(),
+            attempts: 1,
+            last_error: error.to_string(),
+        };
+        if let Err(e) = Self::modify(path, |pending| pending.entries.push(entry)) {
+            warn!("Failed to save pending upload queue: {}", e);
+        }
+    }
+}
+
+pub async fn retry_pending_uploads(config: &UploadConfig, verbosity: Verbosity) -> RetrySummary {
+    let path = PendingUploads::path();
+    if PendingUploads::load_from(&path).is_empty() {
+        return RetrySummary::default();
+    }
+    retry_from(&path, &create_uploaders(config), verbosity).await
+}
+
+/// Uploads run without holding the queue lock; entries enqueued meanwhile are kept when
+/// the results are written back.
+async fn retry_from(path: &Path, uploaders: &[Box<dyn BackupUploader>], verbosity: Verbosity) -> RetrySummary {
+    let mut summary = RetrySummary::default();
+    let mut done: Vec<PendingUpload> = Vec::new();
+    let mut failed: Vec<PendingUpload> = Vec::new();
+    for mut entry in PendingUploads::load_from(path).entries {
+        let file = Path::new(&entry.metadata.file_path);
+        if !file.exists() {
+            warn!("Dropping pending upload of {}: file no longer exists", entry.metadata.file_path);
+            summary.dropped += 1;
+            done.push(entry);
+            continue;
+        }
+        let Some(uploader) = uploaders.iter().find(|u| u.name() == entry.uploader) else {
+            continue;
+        };
+
+        match uploader.upload_silent(&entry.metadata, file, verbosity).await {
+            Ok(_) => {
+                info!("Uploaded pending backup {} to {}", entry.metadata.file_path, entry.uploader);
+                summary.uploaded += 1;
+                done.push(entry);
+            }
+            Err(e) => {
+                entry.attempts += 1;
+                entry.last_error = e.to_string();
+                failed.push(entry);
+            }
+        }
+    }
+
+    let saved = PendingUploads::modify(path, |pending| {
+        pending.entries.retain(|entry| !done.iter().any(|d| d.is_same(entry)));
+        for entry in &mut pending.entries {
+            if let Some(retried) = failed.iter().find(|f| f.is_same(entry)) {
+                entry.attempts = retried.attempts;
+                entry.last_error = retried.last_error.clone();
+            }
+        }
+        summary.remaining = pending.len();
+    });
+    if let Err(e) = saved {
+        warn!("Failed to save pending upload queue: {}", e);
+    }
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::BackupError;
+    use async_trait::async_trait;
+    use chrono::Utc;
+
+    struct FakeUploader {
+        fail: bool,
+    }
+
+    #[async_trait]
+    impl BackupUploader for FakeUploader {
+        async fn upload(&self, _metadata: &BackupMetadata, _file_path: &Path) -> Result<()> {
+            match self.fail {
+                true => Err(BackupError::Upload("unreachable".to_string())),
+                false => Ok(()),
+            }
+        }
+
+        async fn upload_silent(&self, metadata: &BackupMetadata, file_path: &Path, _verbosity: Verbosity) -> Result<()> {
+            self.upload(metadata, file_path).await
+        }
+
+        async fn test_connection(&self) -> Result<()> {
+            Ok(())
+        }
+
+        fn name(&self) -> &'static str {
+            "fake"
+        }
+    }
+
+    fn metadata(file_path: &Path) -> BackupMetadata {
+        BackupMetadata {
+            databases: vec!["app".to_string()],
+            connection_name: "prod".to_string(),
+            timestamp: Utc::now(),
+            file_size: 0,
+            file_hash: None,
+            duration_secs: 0,
+            file_path: file_path.to_string_lossy().to_string(),
+            tags: Vec::new(),
+            binlog_positions: Default::default(),
+        }
+    }
+
+    fn uploaders(fail: bool) -> Vec<Box<dyn BackupUploader>> {
+        vec![Box::new(FakeUploader { fail })]
+    }
+
+    #[test]
+    fn test_enqueue_appends() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("pending_uploads.json");
+        PendingUploads::enqueue_to(&path, "fake", &metadata(&dir.path().join("a.zip")), "timeout");
+        PendingUploads::enqueue_to(&path, "fake", &metadata(&dir.path().join("b.zip")), "timeout");
+
+        let pending = PendingUploads::load_from(&path);
+        assert_eq!(pending.len(), 2);
+        assert_eq!(pending.entries[0].attempts, 1);
+        assert_eq!(pending.entries[1].last_error, "timeout");
+    }
+
+    #[tokio::test]
+    async fn test_retry_success_removes_entry() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("pending_uploads.json");
+        let archive = dir.path().join("a.zip");
+        fs::write(&archive, b"zip").unwrap();
+        PendingUploads::enqueue_to(&path, "fake", &metadata(&archive), "timeout");
+        PendingUploads::enqueue_to(&path, "fake", &metadata(&dir.path().join("gone.zip")), "timeout");
+        PendingUploads::enqueue_to(&path, "other", &metadata(&archive), "timeout");
+
+        let summary = retry_from(&path, &uploaders(false), Verbosity::Quiet).await;
+        assert_eq!((summary.uploaded, summary.dropped, summary.remaining), (1, 1, 1));
+        let pending = PendingUploads::load_from(&path);
+        assert_eq!(pending.entries[0].uploader, "other");
+    }
+
+    #[tokio::test]
+    async fn test_retry_failure_keeps_entry() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("pending_uploads.json");
+        let archive = dir.path().join("a.zip");
+        fs::write(&archive, b"zip").unwrap();
+        PendingUploads::enqueue_to(&path, "fake", &metadata(&archive), "timeout");
+
+        let summary = retry_from(&path, &uploaders(true), Verbosity::Quiet).await;
+        assert_eq!((summary.uploaded, summary.remaining), (0, 1));
+        let pending = PendingUploads::load_from(&path);
+        assert_eq!(pending.entries[0].attempts, 2);
+        assert!(pending.entries[0].last_error.contains("unreachable"));
+    }
+
+    #[tokio::test]
+    async fn test_retry_keeps_entries_enqueued_meanwhile() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("pending_uploads.json");
+        let archive = dir.path().join("a.zip");
+        fs::write(&archive, b"zip").unwrap();
+        PendingUploads::enqueue_to(&path, "fake", &metadata(&archive), "timeout");
+
+        // Stands in for a backup that failed to upload while the retry was running.
+        struct EnqueuingUploader(PathBuf);
+        #[async_trait]
+        impl BackupUploader for EnqueuingUploader {
+            async fn upload(&self, _metadata: &BackupMetadata, file_path: &Path) -> Result<()> {
+                let other = file_path.with_file_name("b.zip");
+                PendingUploads::enqueue_to(&self.0, "fake", &metadata(&other), "timeout");
+                Ok(())
+            }
+
+            async fn upload_silent(&self, metadata: &BackupMetadata, file_path: &Path, _verbosity: Verbosity) -> Result<()> {
+                self.upload(metadata, file_path).await
+            }
+
+            async fn test_connection(&self) -> Result<()> {
+                Ok(())
+            }
+
+            fn name(&self) -> &'static str {
+                "fake"
+            }
+        }
+
+        let uploaders: Vec<Box<dyn BackupUploader>> = vec![Box::new(EnqueuingUploader(path.clone()))];
+        let summary = retry_from(&path, &uploaders, Verbosity::Quiet).await;
+        assert_eq!((summary.uploaded, summary.remaining), (1, 1));
+        let pending = PendingUploads::load_from(&path);
+        assert!(pending.entries[0].metadata.file_path.ends_with("b.zip"));
+    }
+}
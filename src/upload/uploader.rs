@@ -1,21 +1,57 @@
+use crate::database::BinlogPosition;
 use crate::error::Result;
+use crate::log::Verbosity;
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::Path;
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BackupMetadata {
     pub databases: Vec<String>,
     pub connection_name: String,
     pub timestamp: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "is_zero")]
     pub file_size: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub file_hash: Option<String>,
     pub duration_secs: u64,
     pub file_path: String,
+
+    #[serde(default)]
+    pub tags: Vec<String>,
+
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub binlog_positions: BTreeMap<String, BinlogPosition>,
+}
+
+fn is_zero(n: &u64) -> bool {
+    *n == 0
+}
+
+impl BackupMetadata {
+    /// Metadata a backup run embedded as the archive's zip comment.
+    pub fn read_from_archive(archive_path: &Path) -> Option<Self> {
+        let comment = crate::backup::compression::read_comment(archive_path).ok()??;
+        serde_json::from_str(&comment).ok()
+    }
 }
+/// Outcome of handing one archive to one upload target.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UploadStatus {
+
+    pub target: String,
+
+    pub ok: bool,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
 #[async_trait]
 pub trait BackupUploader: Send + Sync {
     async fn upload(&self, metadata: &BackupMetadata, file_path: &Path) -> Result<()>;
-    async fn upload_silent(&self, metadata: &BackupMetadata, file_path: &Path, silent: bool) -> Result<()>;
+    async fn upload_silent(&self, metadata: &BackupMetadata, file_path: &Path, verbosity: Verbosity) -> Result<()>;
     async fn test_connection(&self) -> Result<()>;
     fn name(&self) -> &'static str;
 }
@@ -0,0 +1,307 @@
+use super::state::BackupEntry;
+use crate::error::{BackupError, Result};
+use chrono::{DateTime, SecondsFormat, Utc};
+use rusqlite::types::Value;
+use rusqlite::{params, params_from_iter, Connection};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+const DEFAULT_QUERY_LIMIT: usize = 50;
+
+const ENTRY_COLUMNS: &str =
+    "timestamp, connection_name, databases, success, file_size, duration_secs, error, tags, uploads, file_path";
+
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct HistoryQuery {
+    pub connection: Option<String>,
+    pub success: Option<bool>,
+    pub since: Option<DateTime<Utc>>,
+    pub tag: Option<String>,
+    pub limit: Option<usize>,
+}
+
+impl HistoryQuery {
+    pub fn matches(&self, entry: &BackupEntry) -> bool {
+        if let Some(connection) = &self.connection {
+            if &entry.connection_name != connection {
+                return false;
+            }
+        }
+        if let Some(success) = self.success {
+            if entry.success != success {
+                return false;
+            }
+        }
+        if let Some(since) = self.since {
+            if entry.timestamp < since {
+                return false;
+            }
+        }
+        if let Some(tag) = &self.tag {
+            if !entry.tags.contains(tag) {
+                return false;
+            }
+        }
+        true
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit.unwrap_or(DEFAULT_QUERY_LIMIT)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct HistoryStats {
+    pub total_backups: usize,
+    pub successful_backups: usize,
+    pub total_size: u64,
+}
+
+pub struct HistoryStore {
+    conn: Connection,
+}
+
+impl HistoryStore {
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        Self::init(Connection::open(path)?)
+    }
+
+    fn init(conn: Connection) -> Result<Self> {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS backups (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                timestamp TEXT NOT NULL,
+                connection_name TEXT NOT NULL,
+                databases TEXT NOT NULL,
+                success INTEGER NOT NULL,
+                file_size INTEGER NOT NULL,
+                duration_secs INTEGER NOT NULL,
+                error TEXT
+            );
+            CREATE INDEX IF NOT EXISTS idx_backups_timestamp ON backups (timestamp);
+            CREATE INDEX IF NOT EXISTS idx_backups_connection ON backups (connection_name);",
+        )?;
+        ensure_column(&conn, "tags", "TEXT NOT NULL DEFAULT '[]'")?;
+        ensure_column(&conn, "uploads", "TEXT NOT NULL DEFAULT '[]'")?;
+        ensure_column(&conn, "file_path", "TEXT")?;
+
+        Ok(Self { conn })
+    }
+
+    pub fn insert(&self, entry: &BackupEntry) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO backups (timestamp, connection_name, databases, success, file_size, duration_secs, error, tags, uploads, file_path)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                format_timestamp(&entry.timestamp),
+                entry.connection_name,
+                serde_json::to_string(&entry.databases)?,
+                entry.success,
+                entry.file_size as i64,
+                entry.duration_secs as i64,
+                entry.error,
+                serde_json::to_string(&entry.tags)?,
+                serde_json::to_string(&entry.uploads)?,
+                entry.file_path,
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn query(&self, query: &HistoryQuery) -> Result<Vec<BackupEntry>> {
+        let mut sql = format!("SELECT {} FROM backups WHERE 1 = 1", ENTRY_COLUMNS);
+        let mut args: Vec<Value> = Vec::new();
+
+        if let Some(connection) = &query.connection {
+            sql.push_str(" AND connection_name = ?");
+            args.push(Value::Text(connection.clone()));
+        }
+        if let Some(success) = query.success {
+            sql.push_str(" AND success = ?");
+            args.push(Value::Integer(success as i64));
+        }
+        if let Some(since) = &query.since {
+            sql.push_str(" AND timestamp >= ?");
+            args.push(Value::Text(format_timestamp(since)));
+        }
+        if let Some(tag) = &query.tag {
+            sql.push_str(" AND EXISTS (SELECT 1 FROM json_each(backups.tags) WHERE json_each.value = ?)");
+            args.push(Value::Text(tag.clone()));
+        }
+        sql.push_str(" ORDER BY timestamp DESC LIMIT ?");
+        args.push(Value::Integer(query.limit() as i64));
+
+        let mut stmt = self.conn.prepare(&sql)?;
+        let rows = stmt.query_map(params_from_iter(args), read_entry)?;
+
+        rows.collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(BackupError::from)
+    }
+
+    /// The newest entry of every connection and database list, newest first.
+    pub fn latest_per_database_set(&self) -> Result<Vec<BackupEntry>> {
+        let sql = format!(
+            "SELECT {} FROM backups
+             WHERE id IN (SELECT MAX(id) FROM backups GROUP BY connection_name, databases)
+             ORDER BY timestamp DESC",
+            ENTRY_COLUMNS
+        );
+        let mut stmt = self.conn.prepare(&sql)?;
+        let rows = stmt.query_map([], read_entry)?;
+
+        rows.collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(BackupError::from)
+    }
+
+    pub fn stats(&self) -> Result<HistoryStats> {
+        let stats = self.conn.query_row(
+            "SELECT COUNT(*), COALESCE(SUM(success), 0), COALESCE(SUM(file_size), 0) FROM backups",
+            [],
+            |row| {
+                Ok(HistoryStats {
+                    total_backups: row.get::<_, i64>(0)? as usize,
+                    successful_backups: row.get::<_, i64>(1)? as usize,
+                    total_size: row.get::<_, i64>(2)? as u64,
+                })
+            },
+        )?;
+        Ok(stats)
+    }
+}
+
+/// Maps a row selected with [`ENTRY_COLUMNS`].
+fn read_entry(row: &rusqlite::Row) -> rusqlite::Result<BackupEntry> {
+    let timestamp: String = row.get(0)?;
+    let databases: String = row.get(2)?;
+    let tags: String = row.get(7)?;
+    let uploads: String = row.get(8)?;
+    Ok(BackupEntry {
+        timestamp: DateTime::parse_from_rfc3339(&timestamp)
+            .map(|t| t.with_timezone(&Utc))
+            .unwrap_or_default(),
+        connection_name: row.get(1)?,
+        databases: serde_json::from_str(&databases).unwrap_or_default(),
+        success: row.get(3)?,
+        file_size: row.get::<_, i64>(4)? as u64,
+        duration_secs: row.get::<_, i64>(5)? as u64,
+        error: row.get(6)?,
+        tags: serde_json::from_str(&tags).unwrap_or_default(),
+        uploads: serde_json::from_str(&uploads).unwrap_or_default(),
+        file_path: row.get(9)?,
+        pinned: false,
+    })
+}
+
+fn ensure_column(conn: &Connection, name: &str, definition: &str) -> Result<()> {
+    let mut stmt = conn.prepare("SELECT 1 FROM pragma_table_info('backups') WHERE name = ?1")?;
+    if !stmt.exists([name])? {
+        conn.execute_batch(&format!("ALTER TABLE backups ADD COLUMN {} {}", name, definition))?;
+    }
+    Ok(())
+}
+
+fn format_timestamp(timestamp: &DateTime<Utc>) -> String {
+    timestamp.to_rfc3339_opts(SecondsFormat::Micros, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn entry(connection: &str, success: bool, file_size: u64, age_hours: i64, tags: &[&str]) -> BackupEntry {
+        BackupEntry {
+            timestamp: Utc::now() - Duration::hours(age_hours),
+            connection_name: connection.to_string(),
+            databases: vec!["app".to_string()],
+            success,
+            file_size,
+            duration_secs: 1,
+            error: (!success).then(|| "failed".to_string()),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            uploads: Vec::new(),
+            file_path: None,
+            pinned: false,
+        }
+    }
+
+    fn store() -> HistoryStore {
+        let store = HistoryStore::init(Connection::open_in_memory().unwrap()).unwrap();
+        store.insert(&entry("prod", true, 100, 3, &["nightly"])).unwrap();
+        store.insert(&entry("prod", false, 0, 2, &[])).unwrap();
+        store.insert(&entry("staging", true, 50, 1, &["nightly", "eu"])).unwrap();
+        store
+    }
+
+    fn connections(entries: &[BackupEntry]) -> Vec<&str> {
+        entries.iter().map(|e| e.connection_name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_query_filters() {
+        let store = store();
+
+        let all = store.query(&HistoryQuery::default()).unwrap();
+        assert_eq!(connections(&all), ["staging", "prod", "prod"]);
+        assert_eq!(all[0].tags, ["nightly", "eu"]);
+
+        let prod = HistoryQuery { connection: Some("prod".to_string()), ..Default::default() };
+        assert_eq!(store.query(&prod).unwrap().len(), 2);
+
+        let failed = HistoryQuery { success: Some(false), ..Default::default() };
+        let failed = store.query(&failed).unwrap();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].error.as_deref(), Some("failed"));
+
+        let recent = HistoryQuery { since: Some(Utc::now() - Duration::minutes(150)), ..Default::default() };
+        assert_eq!(connections(&store.query(&recent).unwrap()), ["staging", "prod"]);
+
+        let limited = HistoryQuery { limit: Some(1), ..Default::default() };
+        assert_eq!(connections(&store.query(&limited).unwrap()), ["staging"]);
+    }
+
+    #[test]
+    fn test_query_by_tag() {
+        let store = store();
+
+        let nightly = HistoryQuery { tag: Some("nightly".to_string()), ..Default::default() };
+        assert_eq!(connections(&store.query(&nightly).unwrap()), ["staging", "prod"]);
+
+        let eu = HistoryQuery { tag: Some("eu".to_string()), ..Default::default() };
+        assert_eq!(connections(&store.query(&eu).unwrap()), ["staging"]);
+
+        // Tags are matched as whole JSON array elements, not substrings.
+        let partial = HistoryQuery { tag: Some("night".to_string()), ..Default::default() };
+        assert!(store.query(&partial).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_latest_per_database_set() {
+        let store = store();
+        let mut other = entry("prod", true, 10, 0, &[]);
+        other.databases = vec!["crm".to_string()];
+        store.insert(&other).unwrap();
+
+        let latest = store.latest_per_database_set().unwrap();
+        let keys: Vec<(&str, &str, bool)> = latest
+            .iter()
+            .map(|e| (e.connection_name.as_str(), e.databases[0].as_str(), e.success))
+            .collect();
+        assert_eq!(keys, [("prod", "crm", true), ("staging", "app", true), ("prod", "app", false)]);
+    }
+
+    #[test]
+    fn test_stats() {
+        let empty = HistoryStore::init(Connection::open_in_memory().unwrap()).unwrap();
+        let stats = empty.stats().unwrap();
+        assert_eq!((stats.total_backups, stats.successful_backups, stats.total_size), (0, 0, 0));
+
+        let stats = store().stats().unwrap();
+        assert_eq!((stats.total_backups, stats.successful_backups, stats.total_size), (3, 2, 150));
+    }
+}
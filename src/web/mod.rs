@@ -1,5 +1,7 @@
+mod history;
 mod server;
 mod state;
 
-pub use server::start_server;
-pub use state::{AppState, BackupEntry, ConfigSummary, LogEntry, SchedulerStatus};
+pub use history::HistoryQuery;
+pub use server::{bind_server, serve, start_server};
+pub use state::{AppState, BackupEntry, ConfigSummary, ConnectionCheck, LogEntry, SchedulerStatus};
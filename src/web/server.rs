@@ -1,48 +1,109 @@
-use super::state::AppState;
+use super::history::HistoryQuery;
+use super::state::{AppState, BackupEntry, CurrentOperation};
+use crate::backup::retention;
+use crate::config::{self, WebConfig};
+use crate::database::pattern::glob_match;
 use axum::{
-    extract::State,
-    http::{header, HeaderMap, StatusCode},
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, HeaderValue, Method, StatusCode},
     response::{Html, IntoResponse, Response},
-    routing::get,
+    routing::{get, post},
     Json, Router,
 };
 use base64::{engine::general_purpose::STANDARD, Engine};
-use serde::Serialize;
-use std::sync::Arc;
-use tracing::{error, info};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::sync::{Arc, OnceLock};
+use tower_http::compression::CompressionLayer;
+use tower_http::cors::{AllowOrigin, CorsLayer};
+use tracing::{error, info, warn};
 
 const DASHBOARD_HTML: &str = include_str!("dashboard.html");
 
+// The page is behind auth, so only the browser may cache it, and it revalidates
+// every load; unchanged pages are answered with 304 via the ETag.
+const DASHBOARD_CACHE_CONTROL: &str = "private, no-cache";
+
+static DASHBOARD_ETAG: OnceLock<String> = OnceLock::new();
+
+fn dashboard_etag() -> &'static str {
+    DASHBOARD_ETAG.get_or_init(|| format!("\"{:x}\"", Sha256::digest(DASHBOARD_HTML.as_bytes())))
+}
+
 #[derive(Serialize)]
 struct ApiResponse<T: Serialize> {
     success: bool,
     data: T,
 }
 
-pub async fn start_server(state: Arc<AppState>, port: u16) {
-    let app = Router::new()
+pub async fn start_server(state: Arc<AppState>, web: WebConfig) {
+    match bind_server(&web).await {
+        Ok(listener) => serve(state, web, listener).await,
+        Err(e) => error!("Failed to bind to port {}: {}", web.port, e),
+    }
+}
+
+pub async fn bind_server(web: &WebConfig) -> std::io::Result<tokio::net::TcpListener> {
+    tokio::net::TcpListener::bind(format!("0.0.0.0:{}", web.port)).await
+}
+
+pub async fn serve(state: Arc<AppState>, web: WebConfig, listener: tokio::net::TcpListener) {
+    let port = web.port;
+    dashboard_etag();
+    let mut app = Router::new()
         .route("/", get(dashboard_handler))
         .route("/api/status", get(status_handler))
         .route("/api/history", get(history_handler))
         .route("/api/scheduler", get(scheduler_handler))
-        .with_state(state);
+        .route("/api/jobs", get(jobs_handler))
+        .route("/api/jobs/schedule", get(schedule_handler))
+        .route("/api/jobs/:id/cancel", post(cancel_job_handler))
+        .route("/api/credentials", post(credentials_handler))
+        .route("/api/pin", post(pin_handler))
+        .layer(CompressionLayer::new());
+    if let Some(cors) = cors_layer(&web.cors_origins) {
+        app = app.layer(cors);
+    }
+    let app = app.with_state(state);
 
-    let addr = format!("0.0.0.0:{}", port);
     info!("Starting web dashboard on http://localhost:{}", port);
-
-    let listener = match tokio::net::TcpListener::bind(&addr).await {
-        Ok(l) => l,
-        Err(e) => {
-            error!("Failed to bind to {}: {}", addr, e);
-            return;
-        }
-    };
-
     if let Err(e) = axum::serve(listener, app).await {
         error!("Web server error: {}", e);
     }
 }
 
+// Preflight requests are answered by the layer itself, before `check_auth` runs,
+// so browsers can discover that the Authorization header is allowed.
+fn cors_layer(origins: &[String]) -> Option<CorsLayer> {
+    if origins.is_empty() {
+        return None;
+    }
+
+    let allow_origin = if origins.iter().any(|o| o == "*") {
+        AllowOrigin::any()
+    } else {
+        let values: Vec<HeaderValue> = origins
+            .iter()
+            .filter_map(|origin| match HeaderValue::from_str(origin) {
+                Ok(v) => Some(v),
+                Err(_) => {
+                    warn!("Ignoring invalid CORS origin: {}", origin);
+                    None
+                }
+            })
+            .collect();
+        AllowOrigin::list(values)
+    };
+
+    Some(
+        CorsLayer::new()
+            .allow_origin(allow_origin)
+            .allow_methods([Method::GET, Method::POST])
+            .allow_headers([header::AUTHORIZATION, header::CONTENT_TYPE]),
+    )
+}
+
 async fn check_auth(headers: &HeaderMap, state: &AppState) -> bool {
     let auth_header = match headers.get(header::AUTHORIZATION) {
         Some(h) => h,
@@ -54,6 +115,10 @@ async fn check_auth(headers: &HeaderMap, state: &AppState) -> bool {
         Err(_) => return false,
     };
 
+    if let Some(token) = auth_str.strip_prefix("Bearer ") {
+        return state.check_api_token(token.trim()).await;
+    }
+
     if !auth_str.starts_with("Basic ") {
         return false;
     }
@@ -93,7 +158,104 @@ async fn dashboard_handler(
     if !check_auth(&headers, &state).await {
         return unauthorized();
     }
-    Html(DASHBOARD_HTML).into_response()
+
+    let etag = dashboard_etag();
+    let cache_headers = [
+        (header::ETAG, etag),
+        (header::CACHE_CONTROL, DASHBOARD_CACHE_CONTROL),
+    ];
+    let not_modified = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.split(',').any(|tag| tag.trim() == etag || tag.trim() == "*"));
+    if not_modified {
+        return (StatusCode::NOT_MODIFIED, cache_headers).into_response();
+    }
+    (cache_headers, Html(DASHBOARD_HTML)).into_response()
+}
+
+#[derive(Deserialize)]
+struct CredentialsUpdate {
+    current_password: String,
+    #[serde(default)]
+    username: Option<String>,
+    new_password: String,
+}
+
+/// Rotates the dashboard credentials for the running server and in the saved config.
+async fn credentials_handler(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(update): Json<CredentialsUpdate>,
+) -> Response {
+    if !check_auth(&headers, &state).await {
+        return unauthorized();
+    }
+
+    let (current_username, current_password) = state.credentials().await;
+    if update.current_password != current_password {
+        return (StatusCode::FORBIDDEN, "Current password is incorrect").into_response();
+    }
+    if update.new_password.is_empty() {
+        return (StatusCode::BAD_REQUEST, "New password cannot be empty").into_response();
+    }
+    let username = update
+        .username
+        .filter(|u| !u.is_empty())
+        .unwrap_or(current_username);
+
+    let saved = config::load().and_then(|mut app_config| {
+        config::set_web_credentials(&mut app_config, username.clone(), update.new_password.clone())?;
+        config::save(&app_config)
+    });
+    if let Err(e) = saved {
+        warn!("Failed to change dashboard credentials: {}", e);
+        return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response();
+    }
+
+    state.set_credentials(username.clone(), update.new_password).await;
+    state.add_log("INFO", "Dashboard credentials changed").await;
+    Json(ApiResponse {
+        success: true,
+        data: serde_json::json!({ "username": username }),
+    })
+    .into_response()
+}
+
+#[derive(Deserialize)]
+struct PinUpdate {
+    file_path: String,
+    pinned: bool,
+}
+
+async fn pin_handler(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(update): Json<PinUpdate>,
+) -> Response {
+    if !check_auth(&headers, &state).await {
+        return unauthorized();
+    }
+
+    // Only archives inside the backup directory can be pinned.
+    let backup_dir = state.config_summary.read().await.backup_directory.clone();
+    let archive = match (std::fs::canonicalize(&update.file_path), std::fs::canonicalize(&backup_dir)) {
+        (Ok(archive), Ok(dir)) if archive.starts_with(&dir) && archive.extension().is_some_and(|ext| ext == "zip") => {
+            archive
+        }
+        _ => return (StatusCode::NOT_FOUND, "No such backup archive").into_response(),
+    };
+    if let Err(e) = retention::set_pinned(&archive, update.pinned) {
+        return (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to update pin: {}", e)).into_response();
+    }
+
+    let action = if update.pinned { "Pinned" } else { "Unpinned" };
+    state.add_log("INFO", &format!("{} {}", action, archive.display())).await;
+    Json(ApiResponse {
+        success: true,
+        data: serde_json::json!({ "file_path": update.file_path, "pinned": update.pinned }),
+    })
+    .into_response()
 }
 
 async fn status_handler(
@@ -104,18 +266,20 @@ async fn status_handler(
         return unauthorized();
     }
 
+    let stats = state.history_stats().await;
+    let current_operation = state.current_operation();
     let scheduler = state.scheduler.read().await;
-    let history = state.history.read().await;
     let config = state.config_summary.read().await;
 
-    let total_backups = history.len();
-    let successful_backups = history.iter().filter(|b| b.success).count();
-    let total_size: u64 = history.iter().map(|b| b.file_size).sum();
+    let total_backups = stats.total_backups;
+    let successful_backups = stats.successful_backups;
+    let total_size = stats.total_size;
 
     #[derive(Serialize)]
     struct StatusData {
         scheduler_running: bool,
         next_run: Option<String>,
+        next_run_in_secs: Option<i64>,
         total_backups: usize,
         successful_backups: usize,
         success_rate: f64,
@@ -123,11 +287,19 @@ async fn status_handler(
         database_connections: usize,
         backup_jobs: usize,
         discord_configured: bool,
+        current_operation: Option<CurrentOperation>,
+        operation_remaining_secs: Option<i64>,
     }
 
+    let operation_remaining_secs = current_operation.as_ref().and_then(|op| {
+        let timeout = op.timeout_secs? as i64;
+        Some((timeout - (Utc::now() - op.started_at).num_seconds()).max(0))
+    });
+
     let data = StatusData {
         scheduler_running: scheduler.running,
-        next_run: scheduler.next_run.map(|t| t.format("%Y-%m-%d %H:%M:%S UTC").to_string()),
+        next_run: scheduler.next_run.map(|t| crate::config::format_local(&t, "%Y-%m-%d %H:%M:%S %Z")),
+        next_run_in_secs: scheduler.next_run.map(|t| (t - Utc::now()).num_seconds().max(0)),
         total_backups,
         successful_backups,
         success_rate: if total_backups > 0 {
@@ -139,6 +311,8 @@ async fn status_handler(
         database_connections: config.database_connections,
         backup_jobs: config.backup_jobs,
         discord_configured: config.discord_configured,
+        current_operation,
+        operation_remaining_secs,
     };
 
     Json(ApiResponse { success: true, data }).into_response()
@@ -147,15 +321,122 @@ async fn status_handler(
 async fn history_handler(
     State(state): State<Arc<AppState>>,
     headers: HeaderMap,
+    Query(query): Query<HistoryQuery>,
+) -> Response {
+    if !check_auth(&headers, &state).await {
+        return unauthorized();
+    }
+
+    let history = state.query_history(&query).await;
+    Json(ApiResponse {
+        success: true,
+        data: history,
+    })
+    .into_response()
+}
+
+async fn jobs_handler(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Response {
+    if !check_auth(&headers, &state).await {
+        return unauthorized();
+    }
+
+    Json(ApiResponse {
+        success: true,
+        data: state.jobs.snapshot(),
+    })
+    .into_response()
+}
+
+/// Configured jobs with their next run and last result. Served under `/api/jobs`
+/// rather than as a top-level `/api/schedule`, which reads too much like `/api/scheduler`.
+async fn schedule_handler(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
 ) -> Response {
     if !check_auth(&headers, &state).await {
         return unauthorized();
     }
 
-    let history = state.history.read().await;
+    #[derive(Serialize)]
+    struct ScheduledJob {
+        connection_name: String,
+        databases: Vec<String>,
+        schedule: String,
+        interval_secs: u64,
+        enabled: bool,
+        priority: i32,
+        tags: Vec<String>,
+        next_run: Option<String>,
+        next_run_in_secs: Option<i64>,
+        last_result: Option<BackupEntry>,
+    }
+
+    let jobs = state.config_summary.read().await.jobs.clone();
+    let scheduler = state.scheduler_status().await;
+    let latest = state.latest_results().await;
+    let mut data = Vec::with_capacity(jobs.len());
+    for job in jobs {
+        // Jobs are only checked once per scheduler cycle, so a job is due at the
+        // first cycle after its interval has elapsed.
+        let next_run = match (job.enabled, scheduler.running) {
+            (true, true) => {
+                let due = state
+                    .job_last_run(&job.key())
+                    .map(|last| last + chrono::Duration::seconds(job.schedule.as_seconds() as i64));
+                match (due, scheduler.next_run) {
+                    (Some(due), Some(cycle)) => Some(due.max(cycle)),
+                    (due, cycle) => cycle.or(due),
+                }
+            }
+            _ => None,
+        };
+        let last_result = latest
+            .iter()
+            .find(|entry| {
+                entry.connection_name == job.db_config_name
+                    && (job.databases.is_empty()
+                        || entry
+                            .databases
+                            .iter()
+                            .any(|db| job.databases.iter().any(|pattern| glob_match(pattern, db))))
+            })
+            .cloned();
+        data.push(ScheduledJob {
+            connection_name: job.db_config_name.clone(),
+            databases: job.databases.clone(),
+            schedule: job.schedule.to_string(),
+            interval_secs: job.schedule.as_seconds(),
+            enabled: job.enabled,
+            priority: job.priority,
+            tags: job.tags.clone(),
+            next_run: next_run.map(|t| config::format_local(&t, "%Y-%m-%d %H:%M:%S %Z")),
+            next_run_in_secs: next_run.map(|t| (t - Utc::now()).num_seconds().max(0)),
+            last_result,
+        });
+    }
+
+    Json(ApiResponse { success: true, data }).into_response()
+}
+
+async fn cancel_job_handler(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(id): Path<u64>,
+) -> Response {
+    if !check_auth(&headers, &state).await {
+        return unauthorized();
+    }
+
+    if !state.jobs.cancel(id) {
+        return (StatusCode::NOT_FOUND, "No pending or running job with that id").into_response();
+    }
+    state.add_log("INFO", &format!("Backup job {} cancelled", id)).await;
     Json(ApiResponse {
         success: true,
-        data: history.clone(),
+        data: serde_json::json!({ "id": id }),
     })
     .into_response()
 }
@@ -168,10 +449,9 @@ async fn scheduler_handler(
         return unauthorized();
     }
 
-    let scheduler = state.scheduler.read().await;
     Json(ApiResponse {
         success: true,
-        data: scheduler.clone(),
+        data: state.scheduler_status().await,
     })
     .into_response()
 }
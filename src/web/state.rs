@@ -1,7 +1,18 @@
+use super::history::{HistoryQuery, HistoryStats, HistoryStore};
+use crate::backup::queue::JobQueue;
+use crate::backup::retention::is_pinned;
+use crate::config::BackupJob;
+use crate::database::DumpProgress;
+use crate::error::Result;
+use crate::upload::UploadStatus;
 use chrono::{DateTime, Utc};
 use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::time::Duration;
+use tokio::sync::{Mutex, Notify, RwLock};
+use tracing::warn;
 
 #[derive(Debug, Clone, Serialize)]
 pub struct SchedulerStatus {
@@ -15,6 +26,11 @@ pub struct SchedulerStatus {
     pub connection_name: Option<String>,
 
     pub database_count: usize,
+
+    /// Fraction (0.0 to 1.0) of the running job's tables already dumped.
+    pub progress: Option<f32>,
+
+    pub current_database: Option<String>,
 }
 
 impl Default for SchedulerStatus {
@@ -25,6 +41,8 @@ impl Default for SchedulerStatus {
             interval_secs: 0,
             connection_name: None,
             database_count: 0,
+            progress: None,
+            current_database: None,
         }
     }
 }
@@ -45,6 +63,55 @@ pub struct BackupEntry {
     pub duration_secs: u64,
 
     pub error: Option<String>,
+
+    pub tags: Vec<String>,
+
+    pub uploads: Vec<UploadStatus>,
+
+    pub file_path: Option<String>,
+
+    /// Whether the archive is pinned; looked up when history is queried, not stored.
+    pub pinned: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CurrentOperation {
+    pub connection_name: String,
+    pub database: Option<String>,
+    pub table: Option<String>,
+    pub table_index: usize,
+    pub table_count: usize,
+    pub database_index: usize,
+    pub database_count: usize,
+    pub started_at: DateTime<Utc>,
+    pub timeout_secs: Option<u64>,
+}
+
+impl CurrentOperation {
+    /// Share of the job done, counting each database equally and finished tables within it.
+    pub fn progress(&self) -> Option<f32> {
+        if self.database_count == 0 || self.table_count == 0 {
+            return None;
+        }
+        let within = (self.table_index.saturating_sub(1)) as f32 / self.table_count as f32;
+        Some(((self.database_index.saturating_sub(1)) as f32 + within) / self.database_count as f32)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConnectionCheck {
+    pub checked_at: DateTime<Utc>,
+    pub error: Option<String>,
+}
+
+impl ConnectionCheck {
+    pub fn is_ok(&self) -> bool {
+        self.error.is_none()
+    }
+
+    pub fn age_secs(&self) -> i64 {
+        (Utc::now() - self.checked_at).num_seconds().max(0)
+    }
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -64,7 +131,21 @@ pub struct AppState {
 
     credentials: RwLock<(String, String)>,
 
+    api_token: RwLock<Option<String>>,
+
     pub scheduler_logs: RwLock<Vec<LogEntry>>,
+
+    history_store: Mutex<Option<HistoryStore>>,
+
+    current_operation: std::sync::RwLock<Option<CurrentOperation>>,
+
+    config_changed: Notify,
+
+    connection_checks: std::sync::RwLock<HashMap<String, ConnectionCheck>>,
+
+    job_runs: std::sync::RwLock<HashMap<String, DateTime<Utc>>>,
+
+    pub jobs: JobQueue,
 }
 
 #[derive(Debug, Clone, Serialize, Default)]
@@ -73,6 +154,7 @@ pub struct ConfigSummary {
     pub backup_jobs: usize,
     pub discord_configured: bool,
     pub backup_directory: String,
+    pub jobs: Vec<BackupJob>,
 }
 
 impl AppState {
@@ -83,7 +165,14 @@ impl AppState {
             history: RwLock::new(Vec::new()),
             config_summary: RwLock::new(ConfigSummary::default()),
             credentials: RwLock::new((username, password)),
+            api_token: RwLock::new(None),
             scheduler_logs: RwLock::new(Vec::new()),
+            history_store: Mutex::new(None),
+            current_operation: std::sync::RwLock::new(None),
+            config_changed: Notify::new(),
+            connection_checks: std::sync::RwLock::new(HashMap::new()),
+            job_runs: std::sync::RwLock::new(HashMap::new()),
+            jobs: JobQueue::default(),
         })
     }
 
@@ -92,17 +181,110 @@ impl AppState {
         *creds = (username, password);
     }
 
+    pub async fn credentials(&self) -> (String, String) {
+        self.credentials.read().await.clone()
+    }
+
     pub async fn check_credentials(&self, username: &str, password: &str) -> bool {
         let creds = self.credentials.read().await;
         creds.0 == username && creds.1 == password
     }
 
+    pub async fn set_api_token(&self, token: Option<String>) {
+        *self.api_token.write().await = token.filter(|t| !t.is_empty());
+    }
+
+    pub async fn check_api_token(&self, token: &str) -> bool {
+        self.api_token.read().await.as_deref() == Some(token)
+    }
+
     pub async fn update_scheduler(&self, status: SchedulerStatus) {
         let mut scheduler = self.scheduler.write().await;
         *scheduler = status;
     }
 
+    pub async fn open_history_store(&self, path: &Path) -> Result<()> {
+        let store = HistoryStore::open(path)?;
+        let mut history_store = self.history_store.lock().await;
+        *history_store = Some(store);
+        Ok(())
+    }
+
+    pub async fn reset_history(&self) {
+        *self.history_store.lock().await = None;
+        self.history.write().await.clear();
+    }
+
+    pub async fn query_history(&self, query: &HistoryQuery) -> Vec<BackupEntry> {
+        let stored = match self.history_store.lock().await.as_ref().map(|store| store.query(query)) {
+            Some(Ok(entries)) => Some(entries),
+            Some(Err(e)) => {
+                warn!("Failed to query history store: {}", e);
+                None
+            }
+            None => None,
+        };
+        let mut entries = match stored {
+            Some(entries) => entries,
+            None => {
+                let history = self.history.read().await;
+                history
+                    .iter()
+                    .filter(|e| query.matches(e))
+                    .take(query.limit())
+                    .cloned()
+                    .collect()
+            }
+        };
+        for entry in &mut entries {
+            entry.pinned = entry.file_path.as_deref().is_some_and(|p| is_pinned(Path::new(p)));
+        }
+        entries
+    }
+
+    /// The newest history entry of every connection and database list, newest first.
+    pub async fn latest_results(&self) -> Vec<BackupEntry> {
+        let stored = match self.history_store.lock().await.as_ref().map(|store| store.latest_per_database_set()) {
+            Some(Ok(entries)) => Some(entries),
+            Some(Err(e)) => {
+                warn!("Failed to query history store: {}", e);
+                None
+            }
+            None => None,
+        };
+        let mut entries = match stored {
+            Some(entries) => entries,
+            None => self.history.read().await.iter().cloned().collect(),
+        };
+        for entry in &mut entries {
+            entry.pinned = entry.file_path.as_deref().is_some_and(|p| is_pinned(Path::new(p)));
+        }
+        entries
+    }
+
+    pub async fn history_stats(&self) -> HistoryStats {
+        if let Some(store) = self.history_store.lock().await.as_ref() {
+            match store.stats() {
+                Ok(stats) => return stats,
+                Err(e) => warn!("Failed to read history store stats: {}", e),
+            }
+        }
+
+        let history = self.history.read().await;
+        HistoryStats {
+            total_backups: history.len(),
+            successful_backups: history.iter().filter(|b| b.success).count(),
+            total_size: history.iter().map(|b| b.file_size).sum(),
+        }
+    }
+
     pub async fn add_backup_entry(&self, entry: BackupEntry) {
+        if let Some(store) = self.history_store.lock().await.as_ref() {
+            if let Err(e) = store.insert(&entry) {
+                warn!("Failed to record backup in history store: {}", e);
+            }
+        }
+
         let mut history = self.history.write().await;
         history.insert(0, entry);
         if history.len() > 50 {
@@ -110,6 +292,96 @@ impl AppState {
         }
     }
 
+    pub fn begin_operation(&self, connection_name: &str, timeout: Option<Duration>) {
+        let mut current = self.current_operation.write().unwrap_or_else(|e| e.into_inner());
+        *current = Some(CurrentOperation {
+            connection_name: connection_name.to_string(),
+            database: None,
+            table: None,
+            table_index: 0,
+            table_count: 0,
+            database_index: 0,
+            database_count: 0,
+            started_at: Utc::now(),
+            timeout_secs: timeout.map(|t| t.as_secs()),
+        });
+    }
+
+    pub fn update_operation(&self, progress: &DumpProgress) {
+        let mut current = self.current_operation.write().unwrap_or_else(|e| e.into_inner());
+        if let Some(op) = current.as_mut() {
+            op.database = Some(progress.database.clone());
+            op.table = Some(progress.table.clone());
+            op.table_index = progress.table_index;
+            op.table_count = progress.table_count;
+            op.database_index = progress.database_index;
+            op.database_count = progress.database_count;
+        }
+    }
+
+    /// Tells background tasks that the configuration on disk changed. The signal is
+    /// kept until it is consumed, so a busy scheduler reloads on its next wait.
+    pub fn notify_config_changed(&self) {
+        self.config_changed.notify_one();
+    }
+
+    pub async fn config_changed(&self) {
+        self.config_changed.notified().await;
+    }
+
+    pub fn finish_operation(&self) {
+        let mut current = self.current_operation.write().unwrap_or_else(|e| e.into_inner());
+        *current = None;
+    }
+
+    /// Scheduler status with the progress of the backup currently running, if any.
+    pub async fn scheduler_status(&self) -> SchedulerStatus {
+        let mut status = self.scheduler.read().await.clone();
+        if let Some(op) = self.current_operation() {
+            status.progress = op.progress();
+            status.current_database = op.database;
+        }
+        status
+    }
+
+    pub fn current_operation(&self) -> Option<CurrentOperation> {
+        self.current_operation
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    pub fn record_connection_check(&self, connection_name: &str, error: Option<String>) -> ConnectionCheck {
+        let check = ConnectionCheck {
+            checked_at: Utc::now(),
+            error,
+        };
+        self.connection_checks
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(connection_name.to_string(), check.clone());
+        check
+    }
+
+    /// Records that the scheduler just ran the job with this [`BackupJob::key`].
+    pub fn record_job_run(&self, job_key: &str, at: DateTime<Utc>) {
+        let mut runs = self.job_runs.write().unwrap_or_else(|e| e.into_inner());
+        runs.insert(job_key.to_string(), at);
+    }
+
+    pub fn job_last_run(&self, job_key: &str) -> Option<DateTime<Utc>> {
+        let runs = self.job_runs.read().unwrap_or_else(|e| e.into_inner());
+        runs.get(job_key).copied()
+    }
+
+    pub fn connection_check(&self, connection_name: &str) -> Option<ConnectionCheck> {
+        self.connection_checks
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(connection_name)
+            .cloned()
+    }
+
     pub async fn update_config(&self, summary: ConfigSummary) {
         let mut config = self.config_summary.write().await;
         *config = summary;